@@ -6,6 +6,34 @@ use clap_complete::Shell;
 #[path = "src/pid.rs"]
 mod pid;
 
+#[allow(dead_code)]
+#[path = "src/adjust.rs"]
+mod adjust;
+
+#[allow(dead_code)]
+#[path = "src/cpuset.rs"]
+mod cpuset;
+
+#[allow(dead_code)]
+#[path = "src/duration.rs"]
+mod duration;
+
+#[allow(dead_code)]
+#[path = "src/ioprio.rs"]
+mod ioprio;
+
+#[allow(dead_code)]
+#[path = "src/rlimit.rs"]
+mod rlimit;
+
+#[allow(dead_code)]
+#[path = "src/sched.rs"]
+mod sched;
+
+#[allow(dead_code)]
+#[path = "src/signal.rs"]
+mod signal;
+
 #[allow(dead_code)]
 #[path = "src/cli.rs"]
 mod cli;
@@ -14,7 +42,14 @@ fn main() -> io::Result<()> {
     println!("cargo:rerun-if-env-changed=DIST_DIR");
     println!("cargo:rerun-if-env-changed=PROFILE");
 
+    println!("cargo:rerun-if-changed=src/adjust.rs");
     println!("cargo:rerun-if-changed=src/cli.rs");
+    println!("cargo:rerun-if-changed=src/cpuset.rs");
+    println!("cargo:rerun-if-changed=src/duration.rs");
+    println!("cargo:rerun-if-changed=src/ioprio.rs");
+    println!("cargo:rerun-if-changed=src/rlimit.rs");
+    println!("cargo:rerun-if-changed=src/sched.rs");
+    println!("cargo:rerun-if-changed=src/signal.rs");
 
     let profile = env::var_os("PROFILE");
     if profile != Some("release".into()) {
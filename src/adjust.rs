@@ -0,0 +1,85 @@
+//! Absolute and relative adjustments of numeric values.
+//!
+//! Values with a leading `+` or `-` are relative to the current value, all
+//! others are absolute. A leading `=` forces an absolute value, which is the
+//! only way to give a negative absolute value, e.g. `=-5`.
+
+use std::str::FromStr;
+
+/// An adjustment as given on the command line.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Adjustment<T> {
+    /// Replaces the current value.
+    Absolute(T),
+
+    /// Adds to the current value.
+    Relative(T),
+}
+
+impl<T> Adjustment<T>
+where
+    T: Copy + Into<i64> + TryFrom<i64>,
+{
+    /// Applies this adjustment to the `current` value, clamping the result to
+    /// `min..=max`.
+    pub fn apply(self, current: T, min: T, max: T) -> T {
+        let value = match self {
+            Self::Absolute(value) => value.into(),
+            Self::Relative(delta) => current.into() + delta.into(),
+        };
+
+        let value = value.clamp(min.into(), max.into());
+
+        T::try_from(value).unwrap_or_else(|_| {
+            unreachable!("value has been clamped to the range of its type")
+        })
+    }
+}
+
+impl<T: FromStr> FromStr for Adjustment<T> {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parse = |value: &str| {
+            value
+                .parse::<T>()
+                .map_err(|_| format!("not a number: {value:?}"))
+        };
+
+        match s.split_at_checked(1) {
+            Some(("=", value)) => parse(value).map(Self::Absolute),
+            Some(("+" | "-", _)) => parse(s).map(Self::Relative),
+            _ => parse(s).map(Self::Absolute),
+        }
+    }
+}
+
+// ----------------------------------------------------------------------------
+// tests
+// ----------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::Adjustment;
+
+    #[test]
+    fn parse() {
+        assert_eq!("5".parse(), Ok(Adjustment::Absolute(5_i32)));
+        assert_eq!("=-5".parse(), Ok(Adjustment::Absolute(-5_i32)));
+        assert_eq!("+5".parse(), Ok(Adjustment::Relative(5_i32)));
+        assert_eq!("-3".parse(), Ok(Adjustment::Relative(-3_i32)));
+
+        assert!("".parse::<Adjustment<i32>>().is_err());
+        assert!("=".parse::<Adjustment<i32>>().is_err());
+        assert!("+x".parse::<Adjustment<i32>>().is_err());
+    }
+
+    #[test]
+    fn apply() {
+        assert_eq!(Adjustment::Absolute(5).apply(0, -20, 19), 5);
+        assert_eq!(Adjustment::Relative(5).apply(3, -20, 19), 8);
+        assert_eq!(Adjustment::Relative(5).apply(17, -20, 19), 19);
+        assert_eq!(Adjustment::Relative(-3).apply(-19, -20, 19), -20);
+        assert_eq!(Adjustment::Relative(i32::MAX).apply(0, -20, 19), 19);
+    }
+}
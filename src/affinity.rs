@@ -2,6 +2,12 @@
 
 use anyhow::{Result, anyhow};
 use errno::errno;
+use procfs::process::Process;
+
+use crate::cgroup;
+use crate::cpuset;
+use crate::log;
+use crate::topology;
 
 // For the joy of using a C interface.
 static CPU_SET_SIZE: usize = std::mem::size_of::<libc::cpu_set_t>();
@@ -21,7 +27,7 @@ pub fn get(pid: libc::pid_t) -> Result<Vec<usize>> {
 
     let mut affinity = Vec::new();
 
-    for i in 0..libc::CPU_SETSIZE as usize {
+    for i in 0..cpuset::MAX_CPUS {
         if unsafe { libc::CPU_ISSET(i, &mask) } {
             affinity.push(i);
         }
@@ -32,6 +38,13 @@ pub fn get(pid: libc::pid_t) -> Result<Vec<usize>> {
 
 /// Sets processor affinity aka cpuset.
 pub fn set(pid: libc::pid_t, cpuset: &[usize]) -> Result<()> {
+    if let Some(cpu) = cpuset.iter().find(|&&cpu| cpu >= cpuset::MAX_CPUS) {
+        return Err(anyhow!(
+            "CPU {cpu} exceeds cpu_set_t size {}",
+            cpuset::MAX_CPUS
+        ));
+    }
+
     unsafe {
         let mut mask = std::mem::zeroed();
         libc::CPU_ZERO(&mut mask);
@@ -47,3 +60,89 @@ pub fn set(pid: libc::pid_t, cpuset: &[usize]) -> Result<()> {
 
     Ok(())
 }
+
+/// Returns the CPUs a process may run on: the online CPUs that are permitted
+/// by the cgroup v2 cpuset of the process.
+pub fn permitted(process: &Process, online: &[usize]) -> Result<Vec<usize>> {
+    let mut cpus = online.to_vec();
+
+    if let Some(effective) = cgroup::cpus_effective(process)? {
+        cpus.retain(|cpu| effective.contains(cpu));
+    }
+
+    Ok(cpus)
+}
+
+/// Returns the CPUs requested by a cpuset `change`, or `None` for all CPUs
+/// permitted to each process.
+pub fn requested(change: &cpuset::Change) -> Result<Option<Vec<usize>>> {
+    // free is resolved per process, because cgroups may differ
+    let requested = match change.spec() {
+        cpuset::Spec::Free => None,
+        spec @ cpuset::Spec::Items(..) => Some(topology::resolve(spec)?),
+    };
+
+    if requested.as_ref().is_some_and(Vec::is_empty) {
+        return Err(anyhow!("cpuset resolves to no CPUs"));
+    }
+
+    Ok(requested)
+}
+
+/// Applies a cpuset `change` to a process, with the CPUs `requested` by the
+/// change already resolved or `None` for all permitted ones. Returns the CPUs
+/// that have been set.
+pub fn change(
+    process: &Process,
+    change: &cpuset::Change,
+    requested: Option<&[usize]>,
+    online: &[usize],
+) -> Result<Vec<usize>> {
+    let permitted = permitted(process, online)?;
+
+    let cpus = match change {
+        cpuset::Change::Set(_) => vec![],
+        _ => get(process.pid)?,
+    };
+
+    let cpus = change.apply(&cpus, requested.unwrap_or(&permitted));
+
+    set_permitted(process, cpus, &permitted)
+}
+
+/// Sets the affinity of a process to those `cpus` that are `permitted`, with
+/// a warning about the others. Returns the CPUs that have been set.
+pub fn set_permitted(
+    process: &Process,
+    cpus: Vec<usize>,
+    permitted: &[usize],
+) -> Result<Vec<usize>> {
+    let pid = process.pid;
+
+    let (cpus, dropped): (Vec<usize>, Vec<usize>) =
+        cpus.into_iter().partition(|cpu| permitted.contains(cpu));
+
+    if cpus.is_empty() {
+        let cmd = &process.stat()?.comm;
+
+        return Err(anyhow!(
+            "process {pid} {cmd}: no CPUs left that are online and \
+             permitted by its cgroup"
+        ));
+    }
+
+    if !dropped.is_empty() {
+        let cmd = &process.stat()?.comm;
+
+        log::warn(format!(
+            "process {pid} {cmd}: dropped CPUs {} (offline or not permitted \
+             by cgroup), using {}",
+            cpuset::format_list(&dropped),
+            cpuset::format_list(&cpus),
+        ));
+    }
+
+    set(pid, &cpus)?;
+
+    Ok(cpus)
+}
@@ -0,0 +1,181 @@
+//! Reading cgroup v2 properties of processes.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Result, anyhow};
+use procfs::process::Process;
+
+use crate::topology;
+
+/// Default mount point of the cgroup v2 hierarchy.
+const DEFAULT_MOUNT: &str = "/sys/fs/cgroup";
+
+/// Returns the mount point of the cgroup v2 hierarchy, if it is mounted.
+pub fn mount() -> Option<&'static Path> {
+    static MOUNT: OnceLock<Option<PathBuf>> = OnceLock::new();
+
+    MOUNT.get_or_init(|| find_mount().ok().flatten()).as_deref()
+}
+
+/// Searches the mount info for the cgroup v2 hierarchy, preferring the
+/// default mount point.
+fn find_mount() -> Result<Option<PathBuf>> {
+    let mounts = Process::myself()?
+        .mountinfo()
+        .context("reading mount info failed")?;
+
+    let mut found = None;
+
+    for mount in mounts {
+        if mount.fs_type == "cgroup2" {
+            if mount.mount_point == Path::new(DEFAULT_MOUNT) {
+                return Ok(Some(mount.mount_point));
+            }
+
+            found.get_or_insert(mount.mount_point);
+        }
+    }
+
+    Ok(found)
+}
+
+/// Returns the directory of the cgroup v2 of the given process, if the
+/// hierarchy is mounted.
+pub fn dir(process: &Process) -> Result<Option<PathBuf>> {
+    let Some(mount) = mount() else {
+        return Ok(None);
+    };
+
+    let cgroup = process
+        .cgroups()
+        .with_context(|| format!("reading cgroup of {} failed", process.pid))?
+        .into_iter()
+        .find(|cgroup| cgroup.hierarchy == 0);
+
+    Ok(cgroup
+        .map(|cgroup| mount.join(cgroup.pathname.trim_start_matches('/'))))
+}
+
+/// Returns the CPUs the cgroup of the given process permits, if there is a
+/// cgroup v2 cpuset restricting them.
+pub fn cpus_effective(process: &Process) -> Result<Option<Vec<usize>>> {
+    let (Some(mount), Some(dir)) = (mount(), dir(process)?) else {
+        return Ok(None);
+    };
+
+    // the file only exists where the cpuset controller is enabled, the closest
+    // ancestor that has it determines the effective CPUs
+    for dir in dir.ancestors().take_while(|dir| dir.starts_with(mount)) {
+        let file = dir.join("cpuset.cpus.effective");
+
+        if fs::exists(&file)? {
+            return topology::read_list(file).map(Some);
+        }
+    }
+
+    Ok(None)
+}
+
+/// Returns the memory limit that applies to the given process, i.e. the
+/// lowest `memory.max` of its cgroup v2 and all of its ancestors, if any of
+/// them is limited.
+pub fn memory_max(process: &Process) -> Result<Option<u64>> {
+    let (Some(mount), Some(dir)) = (mount(), dir(process)?) else {
+        return Ok(None);
+    };
+
+    let mut limit = None;
+
+    for dir in dir.ancestors().take_while(|dir| dir.starts_with(mount)) {
+        let file = dir.join("memory.max");
+
+        // the file only exists where the memory controller is enabled
+        let Ok(content) = fs::read_to_string(&file) else {
+            continue;
+        };
+
+        let content = content.trim();
+
+        if content == "max" {
+            continue;
+        }
+
+        let value = content.parse::<u64>().with_context(|| {
+            format!("parsing {} failed: {content:?}", file.display())
+        })?;
+
+        limit = Some(limit.map_or(value, |limit: u64| limit.min(value)));
+    }
+
+    Ok(limit)
+}
+
+/// Returns the PIDs of all processes in the given cgroup and its
+/// descendants.
+pub fn procs(dir: &Path) -> Result<Vec<i32>> {
+    let file = dir.join("cgroup.procs");
+
+    let content = fs::read_to_string(&file)
+        .with_context(|| format!("reading {} failed", file.display()))?;
+
+    let mut pids = content
+        .lines()
+        .filter_map(|line| line.parse().ok())
+        .collect::<Vec<_>>();
+
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+
+        if entry.file_type()?.is_dir() {
+            pids.extend(procs(&entry.path())?);
+        }
+    }
+
+    Ok(pids)
+}
+
+/// Returns true if the given cgroup can be frozen.
+pub fn can_freeze(dir: &Path) -> bool {
+    // the root cgroup has no freezer
+    mount().is_some_and(|mount| dir != mount)
+        && dir.join("cgroup.freeze").exists()
+}
+
+/// Returns true if the given cgroup is frozen.
+pub fn is_frozen(dir: &Path) -> Result<bool> {
+    let file = dir.join("cgroup.events");
+
+    let content = fs::read_to_string(&file)
+        .with_context(|| format!("reading {} failed", file.display()))?;
+
+    Ok(content.lines().any(|line| line == "frozen 1"))
+}
+
+/// Freezes or thaws the given cgroup and waits until the kernel reports it
+/// done.
+pub fn freeze(dir: &Path, frozen: bool, timeout: Duration) -> Result<()> {
+    let file = dir.join("cgroup.freeze");
+
+    fs::write(&file, if frozen { "1" } else { "0" })
+        .with_context(|| format!("writing {} failed", file.display()))?;
+
+    let deadline = Instant::now() + timeout;
+
+    while is_frozen(dir)? != frozen {
+        if Instant::now() >= deadline {
+            return Err(anyhow!(
+                "{} not {} after {timeout:?}",
+                dir.display(),
+                if frozen { "frozen" } else { "thawed" }
+            ));
+        }
+
+        thread::sleep(Duration::from_millis(10));
+    }
+
+    Ok(())
+}
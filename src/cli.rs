@@ -1,8 +1,10 @@
 use std::io::{IsTerminal, stdin};
 
-use clap::{Arg, ArgAction, Command};
+use clap::{Arg, ArgAction, ArgGroup, Command};
 use clap::{crate_description, crate_name, crate_version};
 
+use crate::adjust::Adjustment;
+
 pub const SUBCOMMAND_REQUIRED: &str =
     "CLI argument parser should have been set up to require a subcommand";
 
@@ -16,6 +18,10 @@ pub fn build() -> Command {
         .disable_version_flag(true)
         .infer_subcommands(true)
         .subcommand_required(true)
+        .subcommand(cmd_daemon())
+        .subcommand(cmd_rules())
+        .subcommand(cmd_run())
+        .subcommand(cmd_topology())
         .subcommand(cmd_tree())
         .arg(arg_help())
         .arg(arg_version())
@@ -25,14 +31,117 @@ pub fn build() -> Command {
 // commands
 // ----------------------------------------------------------------------------
 
+fn cmd_daemon() -> Command {
+    Command::new("daemon")
+        .arg(arg_help())
+        .arg(arg_interval().default_value("5s"))
+        .arg(arg_rules().long("rules"))
+        .arg(arg_verbose())
+        .about("apply rules to matching processes")
+        .long_about(
+            "Apply rules to matching processes. All processes are scanned \
+             every interval, and each new process gets the settings of the \
+             first rule that matches it, on all of its threads. Processes \
+             are handled again only after they execute another program, so \
+             settings changed by hand stick. Changes are logged to stderr. \
+             See 'rules check' for the format of the rules file.",
+        )
+}
+
+fn cmd_rules() -> Command {
+    Command::new("rules")
+        .about("rules file commands")
+        .arg_required_else_help(true)
+        .disable_help_flag(true)
+        .subcommand_required(true)
+        .subcommand(cmd_rules_check())
+}
+
+fn cmd_run() -> Command {
+    Command::new("run")
+        .arg(arg_help())
+        .arg(
+            arg_cpuset()
+                .long("affinity")
+                .value_name("CPUS")
+                .required(false),
+        )
+        .arg(arg_ioprio().long("ioprio").required(false))
+        .arg(arg_niceness().long("nice").required(false))
+        .arg(
+            arg_oom_score_adj()
+                .long("oom-score-adj")
+                .value_name("ADJ")
+                .required(false),
+        )
+        .arg(arg_usage())
+        .arg(arg_verbose())
+        .arg(arg_command())
+        .about("run a command with modified settings")
+        .long_about(
+            "Run a command with modified settings. They are applied before \
+             the command is executed, so the command and all of its \
+             descendants inherit them from the start. Relative values are \
+             relative to the settings of psfu itself.",
+        )
+}
+
+fn cmd_topology() -> Command {
+    Command::new("topology")
+        .about("show CPU topology")
+        .long_about(
+            "Show CPU topology: online, offline and isolated CPUs, hybrid \
+             core types, and per socket the NUMA nodes, L3 cache domains and \
+             cores with their SMT siblings and caches. The identifiers like \
+             node:1 or core:3 can be used as cpusets for affinity.",
+        )
+        .disable_help_flag(true)
+        .arg(arg_help())
+}
+
 fn cmd_tree() -> Command {
     Command::new("tree")
         .about("process tree commands")
+        // keep the abbreviation unambiguous with topology
+        .alias("t")
         .arg_required_else_help(true)
         .disable_help_flag(true)
         .subcommand_required(true)
+        .subcommand(cmd_tree_enforce())
         .subcommand(cmd_tree_modify())
+        .subcommand(cmd_tree_resume())
         .subcommand(cmd_tree_show())
+        .subcommand(cmd_tree_signal())
+        .subcommand(cmd_tree_suspend())
+        .subcommand(cmd_tree_terminate())
+        .subcommand(cmd_tree_wait())
+}
+
+fn cmd_tree_enforce() -> Command {
+    Command::new("enforce")
+        .about("keep modifying processes, including new ones")
+        .long_about(
+            "Keep modifying processes, like modify does once. The process \
+             tree is read again every interval, and tasks that are new \
+             since, like forked children, are modified as well. Tasks whose \
+             setting has been changed by someone else since are modified \
+             again. Stops when the roots of all trees have exited. \
+             Relative adjustments, including cpusets with a leading +, - or \
+             &, and --distribute are not supported.",
+        )
+        .arg_required_else_help(true)
+        .disable_help_flag(true)
+        .subcommand_required(true)
+        .arg(arg_help())
+        .arg(arg_interval())
+        .subcommand(cmd_modify_affinity())
+        .subcommand(cmd_modify_ioprio())
+        .subcommand(cmd_modify_limit())
+        .subcommand(cmd_modify_nice())
+        .subcommand(cmd_modify_numa())
+        .subcommand(cmd_modify_oom_score_adj())
+        .subcommand(cmd_modify_sched())
+        .subcommand(cmd_modify_uclamp())
 }
 
 fn cmd_tree_modify() -> Command {
@@ -42,21 +151,34 @@ fn cmd_tree_modify() -> Command {
         .disable_help_flag(true)
         .subcommand_required(true)
         .subcommand(cmd_modify_affinity())
+        .subcommand(cmd_modify_ioprio())
+        .subcommand(cmd_modify_limit())
         .subcommand(cmd_modify_nice())
+        .subcommand(cmd_modify_numa())
         .subcommand(cmd_modify_oom_score_adj())
+        .subcommand(cmd_modify_sched())
+        .subcommand(cmd_modify_uclamp())
 }
 
 fn cmd_tree_show() -> Command {
     Command::new("show")
         .about("show processes")
+        // keep the abbreviation unambiguous with signal and suspend
+        .alias("s")
         .arg_required_else_help(true)
         .disable_help_flag(true)
         .subcommand_required(true)
         .subcommand(cmd_show_affinity())
         .subcommand(cmd_show_backtrace())
+        .subcommand(cmd_show_ioprio())
+        .subcommand(cmd_show_limits())
         .subcommand(cmd_show_nice())
+        .subcommand(cmd_show_numa())
         .subcommand(cmd_show_oom_score())
         .subcommand(cmd_show_oom_score_adj())
+        .subcommand(cmd_show_placement())
+        .subcommand(cmd_show_sched())
+        .subcommand(cmd_show_sched_attr())
         .subcommand(cmd_show_plain())
 }
 
@@ -64,15 +186,58 @@ fn cmd_tree_show() -> Command {
 // leaf commands
 // ----------------------------------------------------------------------------
 
+/// Returns a class subcommand of `ioprio`, the ones with level.
+fn cmd_ioprio_class(name: &'static str, level: bool) -> Command {
+    let cmd = Command::new(name).arg(arg_help());
+    let cmd = if level { cmd.arg(arg_level()) } else { cmd };
+
+    cmd.arg(arg_pid()).arg(arg_verbose())
+}
+
 fn cmd_modify_affinity() -> Command {
     Command::new("affinity")
         .arg(arg_cpuset())
+        .arg(arg_distribute())
+        .arg(arg_group_size())
         .arg(arg_help())
         .arg(arg_pid())
         .arg(arg_verbose())
         .about("modify process tree affinity (cpuset)")
 }
 
+fn cmd_modify_ioprio() -> Command {
+    Command::new("ioprio")
+        .about("modify process tree I/O scheduling class and priority")
+        .arg_required_else_help(true)
+        .disable_help_flag(true)
+        .subcommand_required(true)
+        .arg(arg_help())
+        .subcommand(
+            cmd_ioprio_class("realtime", true)
+                .alias("rt")
+                .about("realtime class, served first"),
+        )
+        .subcommand(
+            cmd_ioprio_class("best-effort", true)
+                .alias("be")
+                .about("best-effort class, like tasks without a class"),
+        )
+        .subcommand(
+            cmd_ioprio_class("idle", false)
+                .about("idle class, served only when nobody else needs I/O"),
+        )
+}
+
+fn cmd_modify_limit() -> Command {
+    Command::new("limit")
+        .arg(arg_help())
+        .arg(arg_resource().required(true))
+        .arg(arg_limit())
+        .arg(arg_pid())
+        .arg(arg_verbose())
+        .about("modify process tree resource limits (prlimit)")
+}
+
 fn cmd_modify_nice() -> Command {
     Command::new("nice")
         .arg(arg_help())
@@ -82,17 +247,117 @@ fn cmd_modify_nice() -> Command {
         .about("modify process tree nice values")
 }
 
+fn cmd_modify_numa() -> Command {
+    Command::new("numa")
+        .arg(arg_help())
+        .arg(arg_nodes())
+        .arg(arg_pid())
+        .arg(arg_verbose())
+        .about("migrate process tree memory to NUMA nodes")
+}
+
 fn cmd_modify_oom_score_adj() -> Command {
     Command::new("oom_score_adj")
         .arg(arg_help())
         .arg(arg_oom_score_adj())
         .arg(arg_pid())
+        .arg(arg_oom_score_adj_min())
+        .arg(arg_oom_score_adj_max())
+        .arg(arg_protect_root())
         .arg(arg_verbose())
         .about("modify process tree oom score adjustment values")
 }
 
+fn cmd_modify_sched() -> Command {
+    Command::new("sched")
+        .about("modify process tree scheduling policy and priority")
+        .arg_required_else_help(true)
+        .disable_help_flag(true)
+        .subcommand_required(true)
+        .arg(arg_help())
+        .subcommand(
+            cmd_sched_policy("other", false)
+                .alias("normal")
+                .about("default time-sharing policy"),
+        )
+        .subcommand(
+            cmd_sched_policy("batch", false)
+                .about("time-sharing policy for non-interactive tasks"),
+        )
+        .subcommand(
+            cmd_sched_policy("idle", false)
+                .about("policy for tasks to run only when a CPU is idle"),
+        )
+        .subcommand(
+            cmd_sched_policy("fifo", true)
+                .about("realtime policy, first in, first out"),
+        )
+        .subcommand(
+            cmd_sched_policy("rr", true)
+                .about("realtime policy, round robin with time slices"),
+        )
+}
+
+fn cmd_modify_uclamp() -> Command {
+    Command::new("uclamp")
+        .arg(arg_help())
+        .arg(arg_uclamp_min())
+        .arg(arg_uclamp_max())
+        .arg(arg_pid())
+        .arg(arg_verbose())
+        .group(
+            ArgGroup::new("uclamp")
+                .args(["uclamp_min", "uclamp_max"])
+                .required(true)
+                .multiple(true),
+        )
+        .about("modify process tree utilization clamping")
+        .long_about(
+            "Modify process tree utilization clamping. The scheduler treats \
+             tasks as using at least the minimum and at most the maximum \
+             utilization, which steers CPU frequency and task placement on \
+             heterogeneous CPUs. Needs Linux 5.3 with CONFIG_UCLAMP_TASK.",
+        )
+}
+
+fn cmd_rules_check() -> Command {
+    Command::new("check")
+        .arg(arg_help())
+        .arg(arg_rules())
+        .about("check a rules file and print its rules")
+        .long_about(
+            "Check a rules file and print its rules. A rules file has one \
+             [name] section per rule with key = value lines, and lines \
+             starting with # or ; are comments. Matchers are comm (command \
+             name), exe (path of the executable), cmdline (regular \
+             expression on the arguments joined with spaces), user (name or \
+             ID) and cgroup (cgroup v2 path, matching it and all cgroups \
+             below). All matchers of a rule must match. Settings are \
+             affinity, ioprio, nice, oom_score_adj and sched with the same \
+             values as the modify commands, except that values are always \
+             absolute: a leading - is part of a negative value, e.g. nice = \
+             -5 is niceness -5, and a leading + or a relative cpuset is an \
+             error.",
+        )
+}
+
+/// Returns a policy subcommand of `sched`, the realtime ones with priority.
+fn cmd_sched_policy(name: &'static str, realtime: bool) -> Command {
+    let cmd = Command::new(name).arg(arg_help());
+
+    let cmd = if realtime {
+        cmd.arg(arg_priority())
+    } else {
+        cmd
+    };
+
+    cmd.arg(arg_pid()).arg(arg_verbose())
+}
+
 fn cmd_show_affinity() -> Command {
     Command::new("affinity")
+        .arg(arg_check_isolated())
+        .arg(arg_cpuset_format())
         .arg(arg_help())
         .arg(arg_pid())
         .arg(arg_show_arguments())
@@ -111,6 +376,43 @@ fn cmd_show_backtrace() -> Command {
         .about("show process tree with backtrace")
 }
 
+fn cmd_show_ioprio() -> Command {
+    Command::new("ioprio")
+        .arg(arg_help())
+        .arg(arg_pid())
+        .arg(arg_show_arguments())
+        .arg(arg_threads())
+        .about("show process tree with I/O scheduling class and priority")
+}
+
+fn cmd_show_limits() -> Command {
+    let resources = crate::rlimit::Resource::ALL.map(|resource| {
+        Command::new(resource.name())
+            .arg(arg_help())
+            .arg(arg_pid())
+            .arg(arg_show_arguments())
+            .arg(arg_threads())
+            .about(resource.description())
+    });
+
+    Command::new("limits")
+        .args_conflicts_with_subcommands(true)
+        .subcommand_negates_reqs(true)
+        .subcommand_help_heading("Resources")
+        .subcommand_value_name("RESOURCE")
+        .arg(arg_help())
+        .arg(arg_pid())
+        .arg(arg_show_arguments())
+        .arg(arg_threads())
+        .subcommands(resources)
+        .about("show process tree with resource limits")
+        .long_about(
+            "Show process tree with soft and hard resource limits as \
+             soft:hard, of all resources or only of the given one, e.g. \
+             limits nofile 1234.",
+        )
+}
+
 fn cmd_show_nice() -> Command {
     Command::new("nice")
         .arg(arg_help())
@@ -121,10 +423,20 @@ fn cmd_show_nice() -> Command {
         .about("show process tree with nice values")
 }
 
+fn cmd_show_numa() -> Command {
+    Command::new("numa")
+        .arg(arg_help())
+        .arg(arg_pid())
+        .arg(arg_show_arguments())
+        .arg(arg_threads())
+        .about("show process tree with resident pages per NUMA node")
+}
+
 fn cmd_show_oom_score() -> Command {
     Command::new("oom_score")
         .arg(arg_help())
         .arg(arg_pid())
+        .arg(arg_ranked())
         .arg(arg_show_arguments())
         .arg(arg_threads())
         .about("show process tree with oom score")
@@ -139,6 +451,44 @@ fn cmd_show_oom_score_adj() -> Command {
         .about("show process tree with oom score adjustment")
 }
 
+fn cmd_show_placement() -> Command {
+    Command::new("placement")
+        .arg(arg_help())
+        .arg(arg_pid())
+        .arg(arg_show_arguments())
+        .arg(arg_threads())
+        .about("show process tree with the CPU and NUMA node tasks run on")
+        .long_about(
+            "Show process tree with the CPU each task last ran on and the \
+             NUMA node of that CPU. Tasks running on a different node than \
+             the one holding most of their memory are flagged.",
+        )
+}
+
+fn cmd_show_sched() -> Command {
+    Command::new("sched")
+        .arg(arg_help())
+        .arg(arg_pid())
+        .arg(arg_show_arguments())
+        .arg(arg_threads())
+        .about("show process tree with scheduling policy and priority")
+}
+
+fn cmd_show_sched_attr() -> Command {
+    Command::new("sched_attr")
+        .visible_alias("sched-attr")
+        .arg(arg_help())
+        .arg(arg_pid())
+        .arg(arg_show_arguments())
+        .arg(arg_threads())
+        .about("show process tree with extended scheduling attributes")
+        .long_about(
+            "Show process tree with extended scheduling attributes, i.e. \
+             policy, nice value or priority, deadline parameters, custom \
+             time slice and utilization clamping.",
+        )
+}
+
 fn cmd_show_plain() -> Command {
     Command::new("plain")
         .arg(arg_help())
@@ -148,18 +498,184 @@ fn cmd_show_plain() -> Command {
         .about("show process tree")
 }
 
+fn cmd_tree_resume() -> Command {
+    Command::new("resume")
+        .arg(arg_help())
+        .arg(arg_pid())
+        .arg(arg_verbose())
+        .about("resume all processes of a suspended tree")
+        .long_about(
+            "Resume all processes of a suspended tree, by thawing its cgroup \
+             if it is frozen, otherwise by sending SIGCONT to all processes, \
+             parents before their children.",
+        )
+}
+
+fn cmd_tree_signal() -> Command {
+    Command::new("signal")
+        .arg(arg_help())
+        .arg(arg_signal())
+        .arg(arg_pid())
+        .arg(arg_dry_run())
+        .arg(arg_order())
+        .arg(arg_verbose())
+        .about("send a signal to all processes of a tree")
+        .long_about(
+            "Send a signal to all processes of a tree, regardless of their \
+             process group, in the order given with --order. Threads are \
+             skipped, signals are delivered to whole processes.",
+        )
+}
+
+fn cmd_tree_suspend() -> Command {
+    Command::new("suspend")
+        .arg(arg_help())
+        .arg(arg_pid())
+        .arg(arg_verbose())
+        .about("suspend all processes of a tree")
+        .long_about(
+            "Suspend all processes of a tree. If the tree owns its cgroup v2, \
+             i.e. no other processes are in it, the cgroup is frozen \
+             atomically. Otherwise all processes get SIGSTOP, parents before \
+             their children, repeatedly until no new children show up.",
+        )
+}
+
+fn cmd_tree_terminate() -> Command {
+    Command::new("terminate")
+        .arg(arg_help())
+        .arg(arg_grace())
+        .arg(arg_pid())
+        .arg(arg_verbose())
+        .about("terminate all processes of a tree")
+        .long_about(
+            "Terminate all processes of a tree, including processes that are \
+             forked in the meantime. Sends SIGTERM followed by SIGCONT to \
+             all of them, so stopped processes terminate too, then SIGKILL \
+             to all that are still alive after the grace period. \
+             Exits with an error if any process survives.",
+        )
+}
+
+fn cmd_tree_wait() -> Command {
+    Command::new("wait")
+        .arg(arg_help())
+        .arg(arg_pid())
+        .arg(arg_timeout())
+        .about("wait until all processes of a tree have exited")
+        .long_about(
+            "Wait until all processes of a tree have exited, including \
+             descendants that have been reparented after their parents \
+             exited, and print the processes that exited last. Exits with \
+             124 if the timeout expires first.",
+        )
+}
+
 // ----------------------------------------------------------------------------
 // arguments
 // ----------------------------------------------------------------------------
 
+fn arg_check_isolated() -> Arg {
+    Arg::new("check_isolated")
+        .long("check-isolated")
+        .action(ArgAction::SetTrue)
+        .help("flag tasks that may run on isolated CPUs")
+        .long_help(
+            "Flag tasks whose affinity overlaps the isolated CPUs, i.e. \
+             isolcpus and nohz_full, including the housekeeping CPUs they \
+             spill to, and exit with an error if there are any.",
+        )
+}
+
+fn arg_command() -> Arg {
+    Arg::new("command")
+        .help("command with its arguments")
+        .required(true)
+        .num_args(1..)
+        .trailing_var_arg(true)
+        .value_parser(clap::value_parser!(std::ffi::OsString))
+}
+
 fn arg_cpuset() -> Arg {
     Arg::new("cpuset")
-        .help("single integer or 'free' for all")
+        .help("CPU list like 0-3,8 or node:0, or 'free'; prefix +, - or &")
+        .long_help(
+            "CPU list in kernel cpulist format, e.g. 0-3,8,10-11 or 0-15:2, \
+             or 'free' for all online CPUs permitted by the cgroup of each \
+             process. Requested CPUs that are offline or not permitted are \
+             dropped with a warning. Items may also select CPUs by topology: \
+             node:N, socket:N, core:N (all SMT siblings) and l3:N, where N \
+             is a list of identifiers like 0-1. The filter smt:first, \
+             smt:last or smt:N keeps only that sibling of each core. \
+             Prefix with + to add to, - to remove from, or & to intersect \
+             with the current cpuset of each task, e.g. -0 or &node:0.",
+        )
         .required(true)
+        .allow_hyphen_values(true)
         .action(ArgAction::Set)
         .value_parser(is_cpuset)
 }
 
+fn arg_cpuset_format() -> Arg {
+    Arg::new("format")
+        .long("format")
+        .short('f')
+        .help("cpuset format")
+        .long_help(
+            "Format of the cpuset: a kernel-style cpulist like 0-63,96-127, \
+             a hex mask of comma separated 32 bit words like the kernel \
+             shows in /proc/<pid>/status, or broken down per NUMA node, \
+             like node:0 for all CPUs of node 0 or node:1=8-11 for some.",
+        )
+        .action(ArgAction::Set)
+        .value_parser(["list", "mask", "numa"])
+        .default_value("list")
+}
+
+fn arg_distribute() -> Arg {
+    Arg::new("distribute")
+        .long("distribute")
+        .help("give each task its own CPU instead of the whole cpuset")
+        .long_help(
+            "Give each task, including threads, its own CPU or group of CPUs \
+             from the cpuset instead of the whole cpuset, starting over \
+             when running out of CPUs, and print the assignment. compact \
+             fills all SMT siblings of a core before moving to the next \
+             core of the same socket, scatter spreads over sockets first, \
+             then cores, then SMT siblings, and round-robin hands out CPUs \
+             in numerical order.",
+        )
+        .action(ArgAction::Set)
+        .value_parser(["compact", "scatter", "round-robin"])
+}
+
+fn arg_dry_run() -> Arg {
+    Arg::new("dry_run")
+        .long("dry-run")
+        .action(ArgAction::SetTrue)
+        .help("only print what would be done")
+}
+
+fn arg_grace() -> Arg {
+    Arg::new("grace")
+        .long("grace")
+        .value_name("DURATION")
+        .help("time to exit after SIGTERM, like 30s, 500ms or 2m")
+        .action(ArgAction::Set)
+        .value_parser(is_duration)
+        .default_value("10s")
+}
+
+fn arg_group_size() -> Arg {
+    Arg::new("group_size")
+        .long("group-size")
+        .help("number of CPUs per task when distributing")
+        .action(ArgAction::Set)
+        .requires("distribute")
+        .value_parser(is_group_size)
+        .default_value("1")
+}
+
 fn arg_help() -> Arg {
     Arg::new("help")
         .short('?')
@@ -169,22 +685,126 @@ fn arg_help() -> Arg {
         .long_help("Print help.")
 }
 
+fn arg_interval() -> Arg {
+    Arg::new("interval")
+        .long("interval")
+        .value_name("DURATION")
+        .help("time between checks, like 2s, 500ms or 1m")
+        .action(ArgAction::Set)
+        .value_parser(is_interval)
+        .default_value("2s")
+}
+
+fn arg_ioprio() -> Arg {
+    Arg::new("ioprio")
+        .help("class realtime, best-effort or idle, with level like be:7")
+        .long_help(
+            "I/O scheduling class realtime (rt), best-effort (be) or idle. \
+             The realtime and best-effort classes take a level from 0 \
+             (highest) to 7 (lowest) inclusively, which defaults to 4, e.g. \
+             best-effort:7.",
+        )
+        .required(true)
+        .action(ArgAction::Set)
+        .value_parser(is_ioprio)
+}
+
+fn arg_level() -> Arg {
+    Arg::new("level")
+        .help("I/O level from 0 (highest) to 7 (lowest) inclusively")
+        .required(true)
+        .action(ArgAction::Set)
+        .value_parser(clap::value_parser!(u8).range(0..=7))
+}
+
+fn arg_limit() -> Arg {
+    Arg::new("limit")
+        .help("soft limit and optionally hard limit like 1024:4096")
+        .long_help(
+            "Soft limit and optionally hard limit, separated by a colon, \
+             each a number or unlimited, e.g. 1024:4096 or 0:unlimited. \
+             Without a hard limit, the current hard limit of each process is \
+             kept.",
+        )
+        .required(true)
+        .action(ArgAction::Set)
+        .value_parser(is_limit)
+}
+
 fn arg_niceness() -> Arg {
     Arg::new("niceness")
-        .help("niceness from -20 to 19 inclusively")
+        .help("niceness from -20 to 19 inclusively, or relative like +5")
+        .long_help(
+            "Niceness from -20 to 19 inclusively. With a leading + or - the \
+             niceness of each task is changed relative to its current value \
+             and clamped to -20 to 19, e.g. +5 or -3. Use a leading = for \
+             negative absolute values, e.g. =-5.",
+        )
         .required(true)
+        .allow_negative_numbers(true)
         .action(ArgAction::Set)
         .value_parser(is_niceness)
 }
 
+fn arg_nodes() -> Arg {
+    Arg::new("nodes")
+        .help("NUMA nodes as list like 0-1,3")
+        .required(true)
+        .action(ArgAction::Set)
+        .value_parser(is_nodes)
+}
+
 fn arg_oom_score_adj() -> Arg {
     Arg::new("oom_score_adj")
-        .help("oom score adjustment from -1000 to 1000 inclusively")
+        .help("oom score adjustment from -1000 to 1000, or relative like +200")
+        .long_help(
+            "Oom score adjustment from -1000 to 1000 inclusively. With a \
+             leading + or - the value of each process is changed relative to \
+             its current value, e.g. +200 or -100. Use a leading = for \
+             negative absolute values, e.g. =-500.",
+        )
         .required(true)
+        .allow_negative_numbers(true)
         .action(ArgAction::Set)
         .value_parser(is_oom_score_adj)
 }
 
+fn arg_oom_score_adj_max() -> Arg {
+    Arg::new("oom_score_adj_max")
+        .long("max")
+        .value_name("ADJ")
+        .help("clamp resulting values to at most this")
+        .allow_negative_numbers(true)
+        .action(ArgAction::Set)
+        .value_parser(is_oom_score_adj_value)
+        .default_value("1000")
+}
+
+fn arg_oom_score_adj_min() -> Arg {
+    Arg::new("oom_score_adj_min")
+        .long("min")
+        .value_name("ADJ")
+        .help("clamp resulting values to at least this")
+        .allow_negative_numbers(true)
+        .action(ArgAction::Set)
+        .value_parser(is_oom_score_adj_value)
+        .default_value("-1000")
+}
+
+fn arg_order() -> Arg {
+    Arg::new("order")
+        .long("order")
+        .help("order of the processes")
+        .long_help(
+            "Order of the processes: bottom-up handles children before their \
+             parents, i.e. leaves first, top-down handles parents before \
+             their children.",
+        )
+        .action(ArgAction::Set)
+        .value_parser(["bottom-up", "top-down"])
+        .default_value("bottom-up")
+}
+
 fn arg_pid() -> Arg {
     Arg::new("pid")
         .help("process IDs")
@@ -193,6 +813,61 @@ fn arg_pid() -> Arg {
         .value_parser(is_pid)
 }
 
+fn arg_priority() -> Arg {
+    Arg::new("priority")
+        .help("realtime priority from 1 (lowest) to 99 (highest) inclusively")
+        .required(true)
+        .action(ArgAction::Set)
+        .value_parser(clap::value_parser!(i32).range(1..=99))
+}
+
+fn arg_protect_root() -> Arg {
+    Arg::new("protect_root")
+        .long("protect-root")
+        .action(ArgAction::SetTrue)
+        .help("keep the value of the root processes")
+        .long_help(
+            "Keep the value of the root processes and modify only their \
+             descendants, e.g. with +500 to make them the preferred victims \
+             of the OOM killer.",
+        )
+}
+
+fn arg_ranked() -> Arg {
+    Arg::new("ranked")
+        .long("ranked")
+        .action(ArgAction::SetTrue)
+        .conflicts_with("threads")
+        .help("show flat list ranked by oom score")
+        .long_help(
+            "Show a flat list of all processes in the trees instead, ranked \
+             by oom score, i.e. in the order the OOM killer picks its \
+             victims, with resident memory, oom score adjustment and the \
+             memory limit (memory.max) of their cgroup. Use PID 1 to rank \
+             the whole system.",
+        )
+}
+
+fn arg_resource() -> Arg {
+    Arg::new("resource")
+        .help("resource like nofile, core, memlock or nproc")
+        .long_help(
+            "Resource to limit, named like the options of prlimit(1): as, \
+             core, cpu, data, fsize, locks, memlock, msgqueue, nice, nofile, \
+             nproc, rss, rtprio, rttime, sigpending or stack.",
+        )
+        .action(ArgAction::Set)
+        .value_parser(is_resource)
+}
+
+fn arg_rules() -> Arg {
+    Arg::new("rules")
+        .value_name("FILE")
+        .help("rules file")
+        .required(true)
+        .value_parser(clap::value_parser!(std::path::PathBuf))
+}
+
 fn arg_show_arguments() -> Arg {
     Arg::new("arguments")
         .long("arguments")
@@ -201,6 +876,14 @@ fn arg_show_arguments() -> Arg {
         .help("show arguments")
 }
 
+fn arg_signal() -> Arg {
+    Arg::new("signal")
+        .help("signal name or number like TERM, SIGHUP or 9")
+        .required(true)
+        .action(ArgAction::Set)
+        .value_parser(is_signal)
+}
+
 fn arg_threads() -> Arg {
     Arg::new("threads")
         .long("threads")
@@ -209,6 +892,55 @@ fn arg_threads() -> Arg {
         .help("include threads")
 }
 
+fn arg_timeout() -> Arg {
+    Arg::new("timeout")
+        .long("timeout")
+        .value_name("DURATION")
+        .help("give up after this long, like 30s, 10m or 2h")
+        .action(ArgAction::Set)
+        .value_parser(is_duration)
+}
+
+fn arg_uclamp_max() -> Arg {
+    Arg::new("uclamp_max")
+        .long("max")
+        .value_name("UTIL")
+        .help("maximum utilization from 0 to 1024 inclusively")
+        .action(ArgAction::Set)
+        .value_parser(is_utilization)
+}
+
+fn arg_uclamp_min() -> Arg {
+    Arg::new("uclamp_min")
+        .long("min")
+        .value_name("UTIL")
+        .help("minimum utilization from 0 to 1024 inclusively")
+        .action(ArgAction::Set)
+        .value_parser(is_utilization)
+}
+
+fn arg_usage() -> Arg {
+    Arg::new("usage")
+        .long("usage")
+        .value_name("FORMAT")
+        .num_args(0..=1)
+        .require_equals(true)
+        .default_missing_value("text")
+        .value_parser(["text", "json"])
+        .help("print resource usage of all descendants")
+        .long_help(
+            "Run the command as child subreaper, reap all of its \
+             descendants, including orphaned ones, and print their \
+             aggregated resource usage to stderr as text or json: CPU time, \
+             peak RSS, I/O bytes and the number of processes, broken down \
+             per command name. psfu exits with the exit code of the command. \
+             Usage of processes that are waited for by another descendant is \
+             counted for the command name of that descendant, and they are \
+             not counted as processes. Peak RSS is the maximum of a single \
+             process, not of the sum.",
+        )
+}
+
 fn arg_verbose() -> Arg {
     Arg::new("verbose")
         .long("verbose")
@@ -229,30 +961,69 @@ fn arg_version() -> Arg {
 // value parsers
 // ----------------------------------------------------------------------------
 
-fn is_cpuset(s: &str) -> Result<String, String> {
-    if s == "free" || s.parse::<u64>().is_ok() {
-        Ok(String::from(s))
-    } else {
-        Err(format!("invalid cpuset: {s:?}"))
+fn is_cpuset(s: &str) -> Result<crate::cpuset::Change, String> {
+    s.parse()
+}
+
+fn is_duration(s: &str) -> Result<std::time::Duration, String> {
+    crate::duration::parse(s)
+}
+
+fn is_group_size(s: &str) -> Result<usize, String> {
+    match s.parse::<usize>() {
+        Ok(0) => Err(String::from("group size must be at least 1")),
+        Ok(size) => Ok(size),
+        Err(e) => Err(format!("{s:?}: {e}")),
     }
 }
 
-fn is_niceness(s: &str) -> Result<i32, String> {
-    s.parse::<i32>().map_or_else(
-        |_| Err(format!("not an i32: {s:?}")),
-        |niceness| {
-            if (-20..=19).contains(&niceness) {
-                Ok(niceness)
-            } else {
-                Err(format!(
-                    "not a niceness value between -20 and 19: {niceness}"
-                ))
-            }
-        },
-    )
+fn is_interval(s: &str) -> Result<std::time::Duration, String> {
+    match crate::duration::parse(s)? {
+        interval if interval.is_zero() => {
+            Err(String::from("interval must be greater than 0"))
+        }
+
+        interval => Ok(interval),
+    }
+}
+
+fn is_ioprio(s: &str) -> Result<crate::ioprio::IoPriority, String> {
+    s.parse()
+}
+
+fn is_limit(s: &str) -> Result<crate::rlimit::Setting, String> {
+    s.parse()
+}
+
+fn is_niceness(s: &str) -> Result<Adjustment<i32>, String> {
+    match s.parse()? {
+        Adjustment::Absolute(niceness) if !(-20..=19).contains(&niceness) => {
+            Err(format!(
+                "not a niceness value between -20 and 19: {niceness}"
+            ))
+        }
+
+        adjustment => Ok(adjustment),
+    }
+}
+
+fn is_nodes(s: &str) -> Result<Vec<usize>, String> {
+    crate::cpuset::parse_list(s)
+}
+
+fn is_oom_score_adj(s: &str) -> Result<Adjustment<i16>, String> {
+    match s.parse()? {
+        Adjustment::Absolute(value) if !(-1000..=1000).contains(&value) => {
+            Err(format!(
+                "not an oom score adjustment value between -1000 and 1000: {value}"
+            ))
+        }
+
+        adjustment => Ok(adjustment),
+    }
 }
 
-fn is_oom_score_adj(s: &str) -> Result<i16, String> {
+fn is_oom_score_adj_value(s: &str) -> Result<i16, String> {
     s.parse::<i16>().map_or_else(
         |_| Err(format!("not an i16: {s:?}")),
         |value| {
@@ -267,6 +1038,24 @@ fn is_oom_score_adj(s: &str) -> Result<i16, String> {
     )
 }
 
+fn is_resource(s: &str) -> Result<crate::rlimit::Resource, String> {
+    s.parse()
+}
+
+fn is_signal(s: &str) -> Result<crate::signal::Signal, String> {
+    s.parse()
+}
+
+fn is_utilization(s: &str) -> Result<u32, String> {
+    match s.parse::<u32>() {
+        Ok(value) if value <= crate::sched::UTIL_MAX => Ok(value),
+        _ => Err(format!(
+            "not a utilization value between 0 and {}: {s:?}",
+            crate::sched::UTIL_MAX
+        )),
+    }
+}
+
 fn is_pid(s: &str) -> Result<i32, String> {
     crate::pid::validate(s)
 }
@@ -0,0 +1,434 @@
+//! Parsing and formatting of CPU lists aka cpusets.
+//!
+//! The accepted syntax is the kernel cpulist format, as used by e.g.
+//! `/sys/devices/system/cpu/online` or `taskset --cpu-list`: a comma separated
+//! list of single CPUs (`8`), ranges (`0-3`), ranges with a stride (`0-15:2`)
+//! and ranges with used/group sizes (`0-15:2/4`).
+//!
+//! Additionally, items may select CPUs by topology: `node:N`, `socket:N`,
+//! `core:N` (all SMT siblings of that core) and `l3:N`, where `N` is itself a
+//! cpulist of identifiers. The filter `smt:first`, `smt:last` or `smt:N`
+//! restricts the other items, or all online CPUs if there are none, to the
+//! given SMT sibling of each core. Selectors are resolved at runtime by the
+//! `topology` module.
+//!
+//! When modifying, a cpuset may be prefixed by `+`, `-` or `&` to add CPUs to,
+//! remove CPUs from, or intersect CPUs with the current cpuset of a task.
+
+use std::str::FromStr;
+
+/// Upper bound of CPU numbers, the number of CPUs that fit into the
+/// `cpu_set_t` of the affinity system calls.
+pub const MAX_CPUS: usize = libc::CPU_SETSIZE as usize;
+
+/// A cpuset as given on the command line.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Spec {
+    /// All CPUs.
+    Free,
+
+    /// A union of items, optionally filtered by SMT sibling.
+    Items(Vec<Item>, Option<Smt>),
+}
+
+/// A change of the cpuset of a task as given on the command line.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Change {
+    /// Replaces the current cpuset.
+    Set(Spec),
+
+    /// Adds CPUs to the current cpuset.
+    Add(Spec),
+
+    /// Removes CPUs from the current cpuset.
+    Remove(Spec),
+
+    /// Keeps only those CPUs of the current cpuset that are also in this one.
+    Intersect(Spec),
+}
+
+impl Change {
+    /// Returns the cpuset specification of this change.
+    pub const fn spec(&self) -> &Spec {
+        match self {
+            Self::Set(spec)
+            | Self::Add(spec)
+            | Self::Remove(spec)
+            | Self::Intersect(spec) => spec,
+        }
+    }
+
+    /// Applies this change to the `current` cpuset of a task, with `spec`
+    /// being the CPUs this change resolved to.
+    pub fn apply(&self, current: &[usize], spec: &[usize]) -> Vec<usize> {
+        let mut cpus = match self {
+            Self::Set(_) => spec.to_vec(),
+            Self::Add(_) => [current, spec].concat(),
+            Self::Remove(_) => current
+                .iter()
+                .filter(|cpu| !spec.contains(cpu))
+                .copied()
+                .collect(),
+            Self::Intersect(_) => current
+                .iter()
+                .filter(|cpu| spec.contains(cpu))
+                .copied()
+                .collect(),
+        };
+
+        cpus.sort_unstable();
+        cpus.dedup();
+        cpus
+    }
+}
+
+impl FromStr for Change {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_at_checked(1) {
+            Some(("+", s)) => s.parse().map(Self::Add),
+            Some(("-", s)) => s.parse().map(Self::Remove),
+            Some(("&", s)) => s.parse().map(Self::Intersect),
+            _ => s.parse().map(Self::Set),
+        }
+    }
+}
+
+/// A single item of a cpuset.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Item {
+    /// An explicit, sorted list of CPUs.
+    Cpus(Vec<usize>),
+
+    /// All CPUs of the given topology domains.
+    Select(Domain, Vec<usize>),
+}
+
+/// Topology domains that can be selected by identifier.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Domain {
+    Node,
+    Socket,
+    Core,
+    L3,
+}
+
+impl Domain {
+    /// Returns the keyword used to select this domain.
+    pub const fn keyword(self) -> &'static str {
+        match self {
+            Self::Node => "node",
+            Self::Socket => "socket",
+            Self::Core => "core",
+            Self::L3 => "l3",
+        }
+    }
+}
+
+/// Selects one SMT sibling per core.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Smt {
+    First,
+    Last,
+    Nth(usize),
+}
+
+impl Spec {
+    /// Returns whether this cpuset selects CPUs by topology, and so needs the
+    /// topology to be resolved.
+    pub fn has_selectors(&self) -> bool {
+        match self {
+            Self::Free => false,
+            Self::Items(items, smt) => {
+                smt.is_some()
+                    || items
+                        .iter()
+                        .any(|item| matches!(item, Item::Select(..)))
+            }
+        }
+    }
+}
+
+impl FromStr for Spec {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s == "free" {
+            return Ok(Self::Free);
+        }
+
+        let mut items = vec![];
+        let mut smt = None;
+
+        for item in s.split(',') {
+            let item = item.trim();
+
+            let Some((keyword, value)) = item.split_once(':') else {
+                items.push(Item::Cpus(parse_list(item)?));
+                continue;
+            };
+
+            let domain = match keyword {
+                "node" => Domain::Node,
+                "socket" => Domain::Socket,
+                "core" => Domain::Core,
+                "l3" => Domain::L3,
+
+                "smt" if smt.is_some() => {
+                    return Err(format!("duplicate smt filter in {s:?}"));
+                }
+
+                "smt" => {
+                    smt = Some(match value {
+                        "first" => Smt::First,
+                        "last" => Smt::Last,
+                        n => Smt::Nth(n.parse().map_err(|_| {
+                            format!("invalid smt filter: {value:?}")
+                        })?),
+                    });
+
+                    continue;
+                }
+
+                // a stride like 0-15:2 rather than a keyword
+                _ if keyword.starts_with(|c: char| c.is_ascii_digit()) => {
+                    items.push(Item::Cpus(parse_list(item)?));
+                    continue;
+                }
+
+                _ => return Err(format!("unknown selector: {keyword:?}")),
+            };
+
+            items.push(Item::Select(domain, parse_list(value)?));
+        }
+
+        Ok(Self::Items(items, smt))
+    }
+}
+
+/// Parses a kernel-style cpulist into a sorted list without duplicates.
+pub fn parse_list(s: &str) -> Result<Vec<usize>, String> {
+    let s = s.trim();
+
+    if s.is_empty() {
+        return Err(String::from("empty cpulist"));
+    }
+
+    let mut cpus = vec![];
+
+    for item in s.split(',') {
+        parse_item(item.trim(), &mut cpus)
+            .map_err(|e| format!("invalid cpulist {s:?}: {e}"))?;
+    }
+
+    cpus.sort_unstable();
+    cpus.dedup();
+
+    Ok(cpus)
+}
+
+/// Parses a single cpulist item and appends its CPUs to `cpus`.
+fn parse_item(item: &str, cpus: &mut Vec<usize>) -> Result<(), String> {
+    let (range, group) = match item.split_once(':') {
+        Some((range, group)) => (range, Some(group)),
+        None => (item, None),
+    };
+
+    let (first, last) = match range.split_once('-') {
+        Some((first, last)) => (parse_cpu(first)?, parse_cpu(last)?),
+        None if group.is_none() => {
+            let cpu = parse_cpu(range)?;
+            (cpu, cpu)
+        }
+        None => return Err(format!("stride without range: {item:?}")),
+    };
+
+    if first > last {
+        return Err(format!("descending range: {item:?}"));
+    }
+
+    // stride N is the same as using 1 CPU out of every group of N
+    let (used, size) = match group {
+        None => (1, 1),
+        Some(group) => match group.split_once('/') {
+            Some((used, size)) => (parse_size(used)?, parse_size(size)?),
+            None => (1, parse_size(group)?),
+        },
+    };
+
+    if used > size {
+        return Err(format!("used size exceeds group size: {item:?}"));
+    }
+
+    cpus.extend((first..=last).filter(|cpu| (cpu - first) % size < used));
+
+    Ok(())
+}
+
+/// Formats CPUs as a kernel-style cpulist, e.g. `0-3,8,10-11`.
+pub fn format_list(cpus: &[usize]) -> String {
+    let mut sorted = cpus.to_vec();
+    sorted.sort_unstable();
+    sorted.dedup();
+
+    let mut ranges: Vec<(usize, usize)> = vec![];
+
+    for cpu in sorted {
+        match ranges.last_mut() {
+            Some((_, last)) if *last + 1 == cpu => *last = cpu,
+            _ => ranges.push((cpu, cpu)),
+        }
+    }
+
+    ranges
+        .into_iter()
+        .map(|(first, last)| {
+            if first == last {
+                format!("{first}")
+            } else {
+                format!("{first}-{last}")
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// Formats CPUs as a kernel-style hex mask of comma separated 32 bit words,
+/// e.g. `ff,00000001`.
+pub fn format_mask(cpus: &[usize]) -> String {
+    let Some(max) = cpus.iter().max() else {
+        return String::from("0");
+    };
+
+    let mut words = vec![0_u32; max / 32 + 1];
+
+    for cpu in cpus {
+        words[cpu / 32] |= 1 << (cpu % 32);
+    }
+
+    let mut words = words.into_iter().rev();
+
+    let first = words.next().map(|word| format!("{word:x}"));
+
+    first
+        .into_iter()
+        .chain(words.map(|word| format!("{word:08x}")))
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+fn parse_cpu(s: &str) -> Result<usize, String> {
+    let cpu = s
+        .parse::<usize>()
+        .map_err(|e| format!("invalid CPU {s:?}: {e}"))?;
+
+    if cpu < MAX_CPUS {
+        Ok(cpu)
+    } else {
+        Err(format!(
+            "CPU {cpu} out of range, maximum is {}",
+            MAX_CPUS - 1
+        ))
+    }
+}
+
+fn parse_size(s: &str) -> Result<usize, String> {
+    match s.parse::<usize>() {
+        Ok(0) => Err(String::from("stride or group size must not be 0")),
+        Ok(size) => Ok(size),
+        Err(e) => Err(format!("invalid stride or group size {s:?}: {e}")),
+    }
+}
+
+// ----------------------------------------------------------------------------
+// tests
+// ----------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::{
+        Change, Domain, Item, Smt, Spec, format_list, format_mask, parse_list,
+    };
+
+    #[test]
+    fn parse() {
+        assert_eq!(parse_list("3"), Ok(vec![3]));
+        assert_eq!(parse_list("0-3,8,10-11"), Ok(vec![0, 1, 2, 3, 8, 10, 11]));
+        assert_eq!(parse_list("8,0-2,1"), Ok(vec![0, 1, 2, 8]));
+        assert_eq!(parse_list("0-15:4"), Ok(vec![0, 4, 8, 12]));
+        assert_eq!(parse_list("0-15:2/8"), Ok(vec![0, 1, 8, 9]));
+    }
+
+    #[test]
+    fn parse_invalid() {
+        for s in ["", "x", "1,", "3-1", "-1", "0-3:0", "4:2", "0-7:3/2"] {
+            assert!(parse_list(s).is_err(), "{s:?} should be invalid");
+        }
+
+        assert!(parse_list(&format!("{}", super::MAX_CPUS)).is_err());
+    }
+
+    #[test]
+    fn format() {
+        assert_eq!(format_list(&[]), "");
+        assert_eq!(format_list(&[5]), "5");
+        assert_eq!(format_list(&[11, 0, 1, 2, 3, 8, 10]), "0-3,8,10-11");
+
+        assert_eq!(format_mask(&[]), "0");
+        assert_eq!(format_mask(&[0, 1, 2, 3]), "f");
+        assert_eq!(format_mask(&[0, 32, 33]), "3,00000001");
+    }
+
+    #[test]
+    fn parse_spec() {
+        assert_eq!(
+            "node:1,0-3:2,smt:first".parse(),
+            Ok(Spec::Items(
+                vec![
+                    Item::Select(Domain::Node, vec![1]),
+                    Item::Cpus(vec![0, 2]),
+                ],
+                Some(Smt::First),
+            ))
+        );
+
+        assert_eq!(
+            "smt:1".parse(),
+            Ok(Spec::Items(vec![], Some(Smt::Nth(1))))
+        );
+
+        for s in ["foo:1", "node:", "smt:x", "smt:first,smt:last"] {
+            assert!(s.parse::<Spec>().is_err(), "{s:?} should be invalid");
+        }
+    }
+
+    #[test]
+    fn selectors() {
+        let spec = |s: &str| s.parse::<Spec>().unwrap();
+
+        assert!(!spec("free").has_selectors());
+        assert!(!spec("0-3,8").has_selectors());
+        assert!(!spec("0-15:2/4").has_selectors());
+        assert!(spec("node:0").has_selectors());
+        assert!(spec("0,core:1").has_selectors());
+        assert!(spec("smt:first").has_selectors());
+    }
+
+    #[test]
+    fn change() {
+        let change = |s: &str| s.parse::<Change>().unwrap();
+        let current = [0, 1, 2, 3];
+
+        assert_eq!(change("6-7").apply(&current, &[6, 7]), [6, 7]);
+        assert_eq!(
+            change("+2-5").apply(&current, &[2, 3, 4, 5]),
+            [0, 1, 2, 3, 4, 5]
+        );
+        assert_eq!(change("-0").apply(&current, &[0]), [1, 2, 3]);
+        assert_eq!(change("&node:0").apply(&current, &[2, 3, 4]), [2, 3]);
+
+        assert!("+".parse::<Change>().is_err());
+        assert!("--1".parse::<Change>().is_err());
+    }
+}
@@ -0,0 +1,178 @@
+//! Applying rules to matching processes as they show up.
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::thread;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use clap::ArgMatches;
+use procfs::process::Process;
+
+use crate::adjust::Adjustment;
+use crate::log;
+use crate::rules::{self, Rule, Settings};
+use crate::topology;
+use crate::tree::modify::{self, Modifier};
+
+// ----------------------------------------------------------------------------
+// CLI runner
+// ----------------------------------------------------------------------------
+
+/// Runs `daemon` subcommand.
+pub fn run(args: &ArgMatches) -> Result<()> {
+    let verbose = args.get_flag("verbose");
+
+    let path = args
+        .get_one::<PathBuf>("rules")
+        .expect("rules is a required argument");
+
+    let interval = args
+        .get_one::<Duration>("interval")
+        .copied()
+        .expect("interval has a default value");
+
+    let online = topology::online()?;
+    let rules = rules::load(path)?;
+
+    if verbose {
+        eprintln!("loaded {} rules from {}", rules.len(), path.display());
+    }
+
+    let modifiers = rules
+        .iter()
+        .map(|rule| modifiers(&rule.settings, &online))
+        .collect::<Vec<_>>();
+
+    // processes are handled once, so their settings can still be changed by
+    // hand; their executable is kept to handle them again after they execute
+    // another program, which keeps PID and start time
+    let mut handled: HashMap<(i32, u64), Option<PathBuf>> = HashMap::new();
+
+    loop {
+        let mut seen = HashMap::new();
+
+        for process in procfs::process::all_processes()
+            .context("reading all processes failed")?
+        {
+            // processes may exit while reading them
+            let Ok(process) = process else {
+                continue;
+            };
+
+            let Ok(stat) = process.stat() else {
+                continue;
+            };
+
+            let key = (process.pid, stat.starttime);
+            let exe = process.exe().ok();
+
+            if handled.get(&key) != Some(&exe) {
+                let comm = &stat.comm;
+
+                if let Some((rule, modifiers)) = rules
+                    .iter()
+                    .zip(&modifiers)
+                    .find(|(rule, _)| rule.matches(&process, comm))
+                {
+                    apply(rule, modifiers, &process, comm, verbose);
+                }
+            }
+
+            seen.insert(key, exe);
+        }
+
+        // forget processes that have exited
+        handled = seen;
+
+        thread::sleep(interval);
+    }
+}
+
+// ----------------------------------------------------------------------------
+// helper
+// ----------------------------------------------------------------------------
+
+/// Returns the modifiers of the settings of a rule with their names.
+fn modifiers<'a>(
+    settings: &'a Settings,
+    online: &[usize],
+) -> Vec<(&'static str, Modifier<'a>)> {
+    let mut modifiers = vec![];
+
+    if let Some(affinity) = &settings.affinity {
+        let modifier = modify::set_affinity(
+            &affinity.change,
+            affinity.requested.clone(),
+            online.to_vec(),
+            false,
+        );
+
+        modifiers.push(("affinity", modifier));
+    }
+
+    if let Some(ioprio) = settings.ioprio {
+        modifiers.push(("ioprio", modify::set_ioprio(ioprio, false)));
+    }
+
+    if let Some(nice) = settings.nice {
+        let modifier = modify::set_nice(Adjustment::Absolute(nice), false);
+        modifiers.push(("nice", modifier));
+    }
+
+    if let Some(value) = settings.oom_score_adj {
+        let adjustment = Adjustment::Absolute(value);
+        let modifier =
+            modify::set_oom_score_adj(adjustment, -1000, 1000, false);
+        modifiers.push(("oom_score_adj", modifier));
+    }
+
+    if let Some(scheduling) = settings.sched {
+        modifiers.push(("sched", modify::set_sched(scheduling, false)));
+    }
+
+    modifiers
+}
+
+/// Applies the settings of a rule to a process and logs the changes.
+fn apply(
+    rule: &Rule,
+    modifiers: &[(&str, Modifier)],
+    process: &Process,
+    comm: &str,
+    verbose: bool,
+) {
+    let pid = process.pid;
+
+    if verbose {
+        eprintln!("process {pid} {comm}: matches rule {}", rule.name);
+    }
+
+    for (setting, modifier) in modifiers {
+        let before = modifier.state(process).and_then(Result::ok);
+
+        // failures of processes that have exited in the meantime don't matter
+        if let Err(e) = modifier.apply_threads(process) {
+            if process.is_alive() {
+                log::error(format!(
+                    "process {pid} {comm}: rule {}: {e}",
+                    rule.name
+                ));
+            }
+
+            continue;
+        }
+
+        let after = modifier.state(process).and_then(Result::ok);
+
+        if let (Some(before), Some(after)) = (before, after) {
+            if before != after {
+                eprintln!(
+                    "process {pid} {comm}: rule {}: {setting} {before} -> \
+                     {after}",
+                    rule.name
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,51 @@
+//! Parsing durations like `30s`, `500ms` or `1.5m`.
+
+use std::time::Duration;
+
+/// Units with their length in seconds, longest suffix first.
+const UNITS: [(&str, f64); 4] =
+    [("ms", 0.001), ("s", 1.0), ("m", 60.0), ("h", 3600.0)];
+
+/// Parses a duration, a number without unit is in seconds.
+pub fn parse(s: &str) -> Result<Duration, String> {
+    let (number, seconds) = UNITS
+        .iter()
+        .find_map(|(unit, seconds)| {
+            s.strip_suffix(unit).map(|number| (number, *seconds))
+        })
+        .unwrap_or((s, 1.0));
+
+    let number = number
+        .parse::<f64>()
+        .ok()
+        .filter(|number| number.is_finite() && *number >= 0.0)
+        .ok_or_else(|| format!("not a duration like 30s or 500ms: {s:?}"))?;
+
+    Duration::try_from_secs_f64(number * seconds)
+        .map_err(|e| format!("{s:?}: {e}"))
+}
+
+// ----------------------------------------------------------------------------
+// tests
+// ----------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use super::parse;
+
+    #[test]
+    fn units() {
+        assert_eq!(parse("30"), Ok(Duration::from_secs(30)));
+        assert_eq!(parse("30s"), Ok(Duration::from_secs(30)));
+        assert_eq!(parse("500ms"), Ok(Duration::from_millis(500)));
+        assert_eq!(parse("1.5m"), Ok(Duration::from_secs(90)));
+        assert_eq!(parse("2h"), Ok(Duration::from_secs(7200)));
+
+        assert!(parse("").is_err());
+        assert!(parse("s").is_err());
+        assert!(parse("-1s").is_err());
+        assert!(parse("1d").is_err());
+    }
+}
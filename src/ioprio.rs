@@ -0,0 +1,187 @@
+//! Getting and setting I/O scheduling class and priority aka ioprio.
+
+use std::fmt;
+use std::str::FromStr;
+
+use anyhow::{Result, anyhow};
+use errno::errno;
+
+// These are not part of libc, see linux/ioprio.h.
+const IOPRIO_WHO_PROCESS: libc::c_int = 1;
+const IOPRIO_CLASS_SHIFT: libc::c_int = 13;
+const IOPRIO_CLASS_MASK: libc::c_int = 0x7;
+const IOPRIO_LEVEL_MASK: libc::c_int = 0x7;
+
+/// An I/O scheduling class.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Class {
+    /// No class set, the kernel derives best-effort with a level from nice.
+    None,
+    Realtime,
+    BestEffort,
+    Idle,
+}
+
+impl Class {
+    /// Returns true for the classes that have a level.
+    pub const fn has_level(self) -> bool {
+        matches!(self, Self::Realtime | Self::BestEffort)
+    }
+}
+
+impl fmt::Display for Class {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::None => "none",
+            Self::Realtime => "realtime",
+            Self::BestEffort => "best-effort",
+            Self::Idle => "idle",
+        };
+
+        f.write_str(name)
+    }
+}
+
+impl FromStr for Class {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "realtime" | "rt" => Ok(Self::Realtime),
+            "best-effort" | "be" => Ok(Self::BestEffort),
+            "idle" => Ok(Self::Idle),
+            _ => Err(format!("unknown I/O class: {s:?}")),
+        }
+    }
+}
+
+/// An I/O class with its level, written like `best-effort:4`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct IoPriority {
+    pub class: Class,
+    pub level: u8,
+}
+
+impl IoPriority {
+    fn from_raw(ioprio: libc::c_int) -> Result<Self> {
+        let class = match (ioprio >> IOPRIO_CLASS_SHIFT) & IOPRIO_CLASS_MASK {
+            0 => Class::None,
+            1 => Class::Realtime,
+            2 => Class::BestEffort,
+            3 => Class::Idle,
+            class => return Err(anyhow!("unknown I/O class: {class}")),
+        };
+
+        // The bits between level and class are hints since Linux 6.5, like
+        // command duration limits. They are ignored, so not shown and
+        // cleared when setting a priority.
+        let level = u8::try_from(ioprio & IOPRIO_LEVEL_MASK)?;
+
+        Ok(Self { class, level })
+    }
+
+    fn raw(self) -> libc::c_int {
+        let class = match self.class {
+            Class::None => 0,
+            Class::Realtime => 1,
+            Class::BestEffort => 2,
+            Class::Idle => 3,
+        };
+
+        (class << IOPRIO_CLASS_SHIFT) | libc::c_int::from(self.level)
+    }
+}
+
+impl fmt::Display for IoPriority {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.class.has_level() {
+            write!(f, "{}:{}", self.class, self.level)
+        } else {
+            write!(f, "{}", self.class)
+        }
+    }
+}
+
+impl FromStr for IoPriority {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (class, level) = match s.split_once(':') {
+            Some((class, level)) => (class, Some(level)),
+            None => (s, None),
+        };
+
+        let class = class.parse::<Class>()?;
+
+        // same default as ionice
+        let level = match level {
+            Some(level) => {
+                level.parse::<u8>().map_err(|e| format!("{level:?}: {e}"))?
+            }
+            None if class.has_level() => 4,
+            None => 0,
+        };
+
+        if class.has_level() && level > 7 {
+            Err(format!("not an I/O level from 0 to 7: {level}"))
+        } else if !class.has_level() && level != 0 {
+            Err(format!("{class} has no level"))
+        } else {
+            Ok(Self { class, level })
+        }
+    }
+}
+
+/// Returns the I/O priority of the given task.
+pub fn get(pid: libc::pid_t) -> Result<IoPriority> {
+    let ioprio = unsafe {
+        libc::syscall(libc::SYS_ioprio_get, IOPRIO_WHO_PROCESS, pid)
+    };
+
+    if ioprio == -1 {
+        return Err(anyhow!("ioprio_get: {}", errno()));
+    }
+
+    IoPriority::from_raw(libc::c_int::try_from(ioprio)?)
+}
+
+/// Sets the I/O priority of the given task.
+pub fn set(pid: libc::pid_t, ioprio: IoPriority) -> Result<()> {
+    let result = unsafe {
+        libc::syscall(
+            libc::SYS_ioprio_set,
+            IOPRIO_WHO_PROCESS,
+            pid,
+            ioprio.raw(),
+        )
+    };
+
+    if result == -1 {
+        return Err(anyhow!("for pid {pid} ioprio_set: {}", errno()));
+    }
+
+    Ok(())
+}
+
+// ----------------------------------------------------------------------------
+// tests
+// ----------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::{Class, IoPriority};
+
+    #[test]
+    fn raw() {
+        let ioprio = IoPriority {
+            class: Class::BestEffort,
+            level: 7,
+        };
+
+        assert_eq!(IoPriority::from_raw(ioprio.raw()).ok(), Some(ioprio));
+
+        // best-effort level 7 with command duration limit hint 1
+        let hinted = (2 << 13) | (1 << 3) | 7;
+        assert_eq!(IoPriority::from_raw(hinted).ok(), Some(ioprio));
+    }
+}
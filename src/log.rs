@@ -3,3 +3,7 @@ use clap::crate_name;
 pub fn error<S: AsRef<str>>(msg: S) {
     eprintln!("{}: error: {}", crate_name!(), msg.as_ref());
 }
+
+pub fn warn<S: AsRef<str>>(msg: S) {
+    eprintln!("{}: warning: {}", crate_name!(), msg.as_ref());
+}
@@ -1,20 +1,42 @@
 #![warn(clippy::pedantic, clippy::nursery, clippy::cargo)]
 
+mod adjust;
 mod affinity;
+mod cgroup;
 mod cli;
+mod cpuset;
+mod daemon;
+mod duration;
+mod ioprio;
 mod log;
 mod nice;
+mod numa;
 mod pid;
+mod rlimit;
+mod rules;
+mod run;
+mod sched;
+mod signal;
+mod topology;
 mod tree;
+mod usage;
 mod util;
 
+use std::process::ExitCode;
+
 use anyhow::Result;
 
-fn main() -> Result<()> {
+fn main() -> Result<ExitCode> {
     let args = cli::build().get_matches();
 
     match args.subcommand() {
-        Some(("tree", args)) => tree::run(args),
+        Some(("daemon", args)) => daemon::run(args)?,
+        Some(("rules", args)) => rules::run(args)?,
+        Some(("run", args)) => return run::run(args),
+        Some(("topology", args)) => topology::run(args)?,
+        Some(("tree", args)) => return tree::run(args),
         _ => unreachable!("{}", crate::cli::SUBCOMMAND_REQUIRED),
     }
+
+    Ok(ExitCode::SUCCESS)
 }
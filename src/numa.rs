@@ -0,0 +1,121 @@
+//! NUMA memory placement of processes.
+
+use std::collections::BTreeMap;
+use std::fs;
+
+use anyhow::{Context, Result, anyhow};
+use errno::errno;
+use procfs::process::Process;
+
+/// Number of NUMA nodes that fit into the node masks passed to the kernel.
+const MAX_NODES: usize = 1024;
+
+/// Bits per word of a node mask.
+const WORD_BITS: usize = libc::c_ulong::BITS as usize;
+
+/// Returns the resident memory of a process per NUMA node in bytes, summed
+/// over all mappings in `/proc/<pid>/numa_maps`.
+pub fn memory(pid: i32) -> Result<BTreeMap<usize, u64>> {
+    let path = format!("/proc/{pid}/numa_maps");
+
+    let content = fs::read_to_string(&path)
+        .with_context(|| format!("reading {path} failed"))?;
+
+    let mut nodes = BTreeMap::new();
+
+    for line in content.lines() {
+        let mut page_size = 4;
+        let mut pages = vec![];
+
+        for field in line.split_whitespace() {
+            let Some((key, value)) = field.split_once('=') else {
+                continue;
+            };
+
+            if key == "kernelpagesize_kB" {
+                page_size = value.parse().unwrap_or(page_size);
+                continue;
+            }
+
+            let node = key.strip_prefix('N').map(str::parse::<usize>);
+
+            if let (Some(Ok(node)), Ok(count)) = (node, value.parse::<u64>()) {
+                pages.push((node, count));
+            }
+        }
+
+        for (node, count) in pages {
+            *nodes.entry(node).or_default() += count * page_size * 1024;
+        }
+    }
+
+    Ok(nodes)
+}
+
+/// Returns the NUMA node holding most of the memory of a process, if it has
+/// any.
+pub fn dominant_node(pid: i32) -> Result<Option<usize>> {
+    let memory = memory(pid)?;
+
+    Ok(memory
+        .into_iter()
+        .max_by_key(|(_, bytes)| *bytes)
+        .map(|(node, _)| node))
+}
+
+/// Returns the base page size in bytes.
+pub fn page_size() -> u64 {
+    let size = unsafe { libc::sysconf(libc::_SC_PAGESIZE) };
+
+    u64::try_from(size).unwrap_or(4096)
+}
+
+/// Moves all pages of a process to the given NUMA nodes. Returns the number
+/// of pages that could not be moved.
+pub fn migrate(process: &Process, nodes: &[usize]) -> Result<u64> {
+    let pid = process.pid;
+
+    if let Some(node) = nodes.iter().find(|&&node| node >= MAX_NODES) {
+        return Err(anyhow!("NUMA node {node} exceeds maximum {MAX_NODES}"));
+    }
+
+    // moving from nodes outside of the allowed ones needs CAP_SYS_NICE
+    let allowed = process
+        .status()?
+        .mems_allowed_list
+        .ok_or_else(|| anyhow!("for pid {pid} no Mems_allowed_list"))?;
+
+    let from = mask(allowed.into_iter().flat_map(|(first, last)| {
+        (first..=last).filter_map(|node| usize::try_from(node).ok())
+    }));
+
+    let to = mask(nodes.iter().copied());
+
+    // the kernel expects the number of bits plus one
+    let result = unsafe {
+        libc::syscall(
+            libc::SYS_migrate_pages,
+            pid,
+            MAX_NODES + 1,
+            from.as_ptr(),
+            to.as_ptr(),
+        )
+    };
+
+    u64::try_from(result)
+        .map_err(|_| anyhow!("for pid {pid} migrate_pages: {}", errno()))
+}
+
+/// Returns the node mask with the given nodes set, ignoring nodes beyond
+/// `MAX_NODES`.
+fn mask(
+    nodes: impl IntoIterator<Item = usize>,
+) -> [libc::c_ulong; MAX_NODES / WORD_BITS] {
+    let mut mask = [0; MAX_NODES / WORD_BITS];
+
+    for node in nodes.into_iter().filter(|&node| node < MAX_NODES) {
+        mask[node / WORD_BITS] |= 1 << (node % WORD_BITS);
+    }
+
+    mask
+}
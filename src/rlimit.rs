@@ -0,0 +1,365 @@
+//! Getting and setting resource limits aka rlimits.
+
+use std::fmt;
+use std::str::FromStr;
+
+use anyhow::{Context, Result, anyhow};
+use errno::errno;
+use procfs::process::{LimitValue, Process};
+
+/// A resource that can be limited, named like the options of `prlimit(1)`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Resource {
+    Cpu,
+    Fsize,
+    Data,
+    Stack,
+    Core,
+    Rss,
+    Nproc,
+    Nofile,
+    Memlock,
+    As,
+    Locks,
+    Sigpending,
+    Msgqueue,
+    Nice,
+    Rtprio,
+    Rttime,
+}
+
+impl Resource {
+    /// All resources in the order of `/proc/<pid>/limits`.
+    pub const ALL: [Self; 16] = [
+        Self::Cpu,
+        Self::Fsize,
+        Self::Data,
+        Self::Stack,
+        Self::Core,
+        Self::Rss,
+        Self::Nproc,
+        Self::Nofile,
+        Self::Memlock,
+        Self::As,
+        Self::Locks,
+        Self::Sigpending,
+        Self::Msgqueue,
+        Self::Nice,
+        Self::Rtprio,
+        Self::Rttime,
+    ];
+
+    /// Returns the description of `prlimit(1)`.
+    pub const fn description(self) -> &'static str {
+        match self {
+            Self::Cpu => "CPU time",
+            Self::Fsize => "max file size",
+            Self::Data => "max data size",
+            Self::Stack => "max stack size",
+            Self::Core => "max core file size",
+            Self::Rss => "max resident set size",
+            Self::Nproc => "max number of processes",
+            Self::Nofile => "max number of open files",
+            Self::Memlock => "max locked-in-memory address space",
+            Self::As => "address space limit",
+            Self::Locks => "max number of file locks held",
+            Self::Sigpending => "max number of pending signals",
+            Self::Msgqueue => "max bytes in POSIX mqueues",
+            Self::Nice => "max nice prio allowed to raise",
+            Self::Rtprio => "max real-time priority",
+            Self::Rttime => "timeout for real-time tasks",
+        }
+    }
+
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Cpu => "cpu",
+            Self::Fsize => "fsize",
+            Self::Data => "data",
+            Self::Stack => "stack",
+            Self::Core => "core",
+            Self::Rss => "rss",
+            Self::Nproc => "nproc",
+            Self::Nofile => "nofile",
+            Self::Memlock => "memlock",
+            Self::As => "as",
+            Self::Locks => "locks",
+            Self::Sigpending => "sigpending",
+            Self::Msgqueue => "msgqueue",
+            Self::Nice => "nice",
+            Self::Rtprio => "rtprio",
+            Self::Rttime => "rttime",
+        }
+    }
+
+    // the type of these constants differs between libc implementations
+    fn raw(self) -> i64 {
+        i64::from(match self {
+            Self::Cpu => libc::RLIMIT_CPU,
+            Self::Fsize => libc::RLIMIT_FSIZE,
+            Self::Data => libc::RLIMIT_DATA,
+            Self::Stack => libc::RLIMIT_STACK,
+            Self::Core => libc::RLIMIT_CORE,
+            Self::Rss => libc::RLIMIT_RSS,
+            Self::Nproc => libc::RLIMIT_NPROC,
+            Self::Nofile => libc::RLIMIT_NOFILE,
+            Self::Memlock => libc::RLIMIT_MEMLOCK,
+            Self::As => libc::RLIMIT_AS,
+            Self::Locks => libc::RLIMIT_LOCKS,
+            Self::Sigpending => libc::RLIMIT_SIGPENDING,
+            Self::Msgqueue => libc::RLIMIT_MSGQUEUE,
+            Self::Nice => libc::RLIMIT_NICE,
+            Self::Rtprio => libc::RLIMIT_RTPRIO,
+            Self::Rttime => libc::RLIMIT_RTTIME,
+        })
+    }
+}
+
+impl fmt::Display for Resource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for Resource {
+    type Err = String;
+
+    /// Parses names like `nofile`, `NOFILE` or `RLIMIT_NOFILE`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let name = s.to_ascii_lowercase();
+        let name = name.strip_prefix("rlimit_").unwrap_or(&name);
+
+        Self::ALL
+            .into_iter()
+            .find(|resource| resource.name() == name)
+            .ok_or_else(|| format!("unknown resource: {s:?}"))
+    }
+}
+
+/// A single limit value.
+#[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd)]
+pub enum Value {
+    Limited(u64),
+    Unlimited,
+}
+
+impl Value {
+    const fn from_raw(value: u64) -> Self {
+        if value == libc::RLIM64_INFINITY {
+            Self::Unlimited
+        } else {
+            Self::Limited(value)
+        }
+    }
+
+    const fn raw(self) -> u64 {
+        match self {
+            Self::Limited(value) => value,
+            Self::Unlimited => libc::RLIM64_INFINITY,
+        }
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Limited(value) => write!(f, "{value}"),
+            Self::Unlimited => f.write_str("unlimited"),
+        }
+    }
+}
+
+impl FromStr for Value {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "unlimited" | "infinity" => Ok(Self::Unlimited),
+            _ => s
+                .parse()
+                .map(Self::Limited)
+                .map_err(|_| format!("not a limit: {s:?}")),
+        }
+    }
+}
+
+/// The soft and hard limit of a resource, written like `1024:4096`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Limit {
+    pub soft: Value,
+    pub hard: Value,
+}
+
+impl fmt::Display for Limit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.soft, self.hard)
+    }
+}
+
+/// A new limit as given on the command line, the hard limit is optional.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Setting {
+    pub soft: Value,
+    pub hard: Option<Value>,
+}
+
+impl Setting {
+    /// Returns the new limit, keeping the `current` hard limit if there is
+    /// none in this setting.
+    pub fn apply(self, current: Limit) -> Limit {
+        Limit {
+            soft: self.soft,
+            hard: self.hard.unwrap_or(current.hard),
+        }
+    }
+}
+
+impl FromStr for Setting {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (soft, hard) = match s.split_once(':') {
+            Some((soft, hard)) => (soft.parse()?, Some(hard.parse()?)),
+            None => (s.parse()?, None),
+        };
+
+        match hard {
+            Some(hard) if soft > hard => {
+                Err(format!("soft limit {soft} exceeds hard limit {hard}"))
+            }
+
+            _ => Ok(Self { soft, hard }),
+        }
+    }
+}
+
+/// Returns all limits of the given process from `/proc/<pid>/limits`.
+pub fn read(process: &Process) -> Result<Vec<(Resource, Limit)>> {
+    let limits = process.limits().with_context(|| {
+        format!("reading limits of {} failed", process.pid)
+    })?;
+
+    let limits = [
+        limits.max_cpu_time,
+        limits.max_file_size,
+        limits.max_data_size,
+        limits.max_stack_size,
+        limits.max_core_file_size,
+        limits.max_resident_set,
+        limits.max_processes,
+        limits.max_open_files,
+        limits.max_locked_memory,
+        limits.max_address_space,
+        limits.max_file_locks,
+        limits.max_pending_signals,
+        limits.max_msgqueue_size,
+        limits.max_nice_priority,
+        limits.max_realtime_priority,
+        limits.max_realtime_timeout,
+    ];
+
+    let value = |value| match value {
+        LimitValue::Value(value) => Value::Limited(value),
+        LimitValue::Unlimited => Value::Unlimited,
+    };
+
+    Ok(Resource::ALL
+        .into_iter()
+        .zip(limits)
+        .map(|(resource, limit)| {
+            let limit = Limit {
+                soft: value(limit.soft_limit),
+                hard: value(limit.hard_limit),
+            };
+
+            (resource, limit)
+        })
+        .collect())
+}
+
+/// Returns the limit of a resource of the given process.
+pub fn get(pid: libc::pid_t, resource: Resource) -> Result<Limit> {
+    prlimit(pid, resource, None)
+}
+
+/// Sets the limit of a resource of the given process. Returns the previous
+/// limit.
+pub fn set(
+    pid: libc::pid_t,
+    resource: Resource,
+    limit: Limit,
+) -> Result<Limit> {
+    prlimit(pid, resource, Some(limit))
+}
+
+fn prlimit(
+    pid: libc::pid_t,
+    resource: Resource,
+    limit: Option<Limit>,
+) -> Result<Limit> {
+    let new = limit.map(|limit| libc::rlimit64 {
+        rlim_cur: limit.soft.raw(),
+        rlim_max: limit.hard.raw(),
+    });
+
+    let new = new.as_ref().map_or(std::ptr::null(), std::ptr::from_ref);
+
+    let mut old = libc::rlimit64 {
+        rlim_cur: 0,
+        rlim_max: 0,
+    };
+
+    let result = unsafe {
+        libc::syscall(
+            libc::SYS_prlimit64,
+            pid,
+            resource.raw(),
+            new,
+            &raw mut old,
+        )
+    };
+
+    if result == -1 {
+        return Err(anyhow!("for pid {pid} prlimit {resource}: {}", errno()));
+    }
+
+    Ok(Limit {
+        soft: Value::from_raw(old.rlim_cur),
+        hard: Value::from_raw(old.rlim_max),
+    })
+}
+
+// ----------------------------------------------------------------------------
+// tests
+// ----------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::{Resource, Setting, Value};
+
+    #[test]
+    fn parse() {
+        assert_eq!("nofile".parse(), Ok(Resource::Nofile));
+        assert_eq!("RLIMIT_CORE".parse(), Ok(Resource::Core));
+        assert!("files".parse::<Resource>().is_err());
+
+        assert_eq!(
+            "1024".parse(),
+            Ok(Setting {
+                soft: Value::Limited(1024),
+                hard: None,
+            })
+        );
+
+        assert_eq!(
+            "0:unlimited".parse(),
+            Ok(Setting {
+                soft: Value::Limited(0),
+                hard: Some(Value::Unlimited),
+            })
+        );
+
+        assert!("unlimited:1024".parse::<Setting>().is_err());
+        assert!("1024:".parse::<Setting>().is_err());
+    }
+}
@@ -0,0 +1,503 @@
+//! Rules that map process matchers to settings.
+//!
+//! A rules file consists of sections, one per rule, with `key = value`
+//! lines. Lines starting with `#` or `;` are comments:
+//!
+//! ```ini
+//! [compilers]
+//! comm = cc1plus
+//! nice = 10
+//! ioprio = idle
+//! ```
+//!
+//! All matchers of a rule must match a process. The first rule that matches
+//! wins.
+
+use std::ffi::CString;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result, anyhow};
+use clap::ArgMatches;
+use procfs::process::Process;
+use regex::Regex;
+
+use crate::affinity;
+use crate::cpuset;
+use crate::ioprio::IoPriority;
+use crate::sched::Scheduling;
+
+// ----------------------------------------------------------------------------
+// CLI runner
+// ----------------------------------------------------------------------------
+
+/// Runs `rules` subcommand.
+pub fn run(args: &ArgMatches) -> Result<()> {
+    match args.subcommand() {
+        Some(("check", args)) => run_check(args),
+        _ => unreachable!("{}", crate::cli::SUBCOMMAND_REQUIRED),
+    }
+}
+
+/// Runs `rules check` subcommand.
+fn run_check(args: &ArgMatches) -> Result<()> {
+    let path = args
+        .get_one::<PathBuf>("rules")
+        .expect("rules is a required argument");
+
+    let rules = load(path)?;
+
+    for rule in &rules {
+        println!("{rule}");
+    }
+
+    Ok(())
+}
+
+// ----------------------------------------------------------------------------
+// rules
+// ----------------------------------------------------------------------------
+
+/// A rule with the settings for the processes it matches.
+#[derive(Debug)]
+pub struct Rule {
+    pub name: String,
+    matchers: Vec<Matcher>,
+    pub settings: Settings,
+}
+
+impl Rule {
+    fn new(name: String) -> Self {
+        Self {
+            name,
+            matchers: vec![],
+            settings: Settings::default(),
+        }
+    }
+
+    /// Returns true if all matchers of this rule match the process with the
+    /// given command name.
+    pub fn matches(&self, process: &Process, comm: &str) -> bool {
+        self.matchers
+            .iter()
+            .all(|matcher| matcher.matches(process, comm).unwrap_or(false))
+    }
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let matchers = self
+            .matchers
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>();
+
+        write!(
+            f,
+            "{}: {} -> {}",
+            self.name,
+            matchers.join(" "),
+            self.settings
+        )
+    }
+}
+
+/// Matches a property of a process.
+#[derive(Debug)]
+enum Matcher {
+    /// The command name.
+    Comm(String),
+
+    /// The path of the executable.
+    Exe(PathBuf),
+
+    /// The arguments, joined with spaces.
+    Cmdline(Regex),
+
+    /// The effective user ID.
+    User(u32),
+
+    /// The cgroup v2 path or one of its ancestors, like `/user.slice`.
+    Cgroup(String),
+}
+
+impl Matcher {
+    fn matches(&self, process: &Process, comm: &str) -> Result<bool> {
+        let matches = match self {
+            Self::Comm(name) => name == comm,
+            Self::Exe(path) => &process.exe()? == path,
+            Self::Cmdline(regex) => {
+                regex.is_match(&process.cmdline()?.join(" "))
+            }
+            Self::User(uid) => process.status()?.euid == *uid,
+            Self::Cgroup(path) => process
+                .cgroups()?
+                .into_iter()
+                .find(|cgroup| cgroup.hierarchy == 0)
+                .is_some_and(|cgroup| is_below(&cgroup.pathname, path)),
+        };
+
+        Ok(matches)
+    }
+}
+
+impl fmt::Display for Matcher {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Comm(name) => write!(f, "comm={name}"),
+            Self::Exe(path) => write!(f, "exe={}", path.display()),
+            Self::Cmdline(regex) => write!(f, "cmdline={regex}"),
+            Self::User(uid) => write!(f, "user={uid}"),
+            Self::Cgroup(path) => write!(f, "cgroup={path}"),
+        }
+    }
+}
+
+/// The settings of a rule.
+#[derive(Debug, Default)]
+pub struct Settings {
+    pub affinity: Option<Affinity>,
+    pub ioprio: Option<IoPriority>,
+    pub nice: Option<i32>,
+    pub oom_score_adj: Option<i16>,
+    pub sched: Option<Scheduling>,
+}
+
+impl Settings {
+    const fn is_empty(&self) -> bool {
+        self.affinity.is_none()
+            && self.ioprio.is_none()
+            && self.nice.is_none()
+            && self.oom_score_adj.is_none()
+            && self.sched.is_none()
+    }
+}
+
+impl fmt::Display for Settings {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut settings = vec![];
+
+        if let Some(affinity) = &self.affinity {
+            settings.push(format!("affinity={}", affinity.spec));
+        }
+
+        if let Some(ioprio) = self.ioprio {
+            settings.push(format!("ioprio={ioprio}"));
+        }
+
+        if let Some(nice) = self.nice {
+            settings.push(format!("nice={nice}"));
+        }
+
+        if let Some(oom_score_adj) = self.oom_score_adj {
+            settings.push(format!("oom_score_adj={oom_score_adj}"));
+        }
+
+        if let Some(sched) = self.sched {
+            settings.push(format!("sched={sched}"));
+        }
+
+        write!(f, "{}", settings.join(" "))
+    }
+}
+
+/// An affinity setting with the CPUs it resolves to.
+#[derive(Debug)]
+pub struct Affinity {
+    spec: String,
+    pub change: cpuset::Change,
+
+    /// The CPUs requested by the change, `None` for all permitted ones.
+    pub requested: Option<Vec<usize>>,
+}
+
+// ----------------------------------------------------------------------------
+// parsing
+// ----------------------------------------------------------------------------
+
+/// Reads the rules from a file and resolves their cpusets.
+pub fn load(path: &Path) -> Result<Vec<Rule>> {
+    let content = fs::read_to_string(path)
+        .with_context(|| format!("reading {} failed", path.display()))?;
+
+    let mut rules =
+        parse(&content).map_err(|e| anyhow!("{}:{e}", path.display()))?;
+
+    for rule in &mut rules {
+        if let Some(affinity) = &mut rule.settings.affinity {
+            affinity.requested = affinity::requested(&affinity.change)
+                .with_context(|| {
+                    format!("{}: rule {}", path.display(), rule.name)
+                })?;
+        }
+    }
+
+    Ok(rules)
+}
+
+/// Parses rules. Errors start with the line number.
+fn parse(content: &str) -> Result<Vec<Rule>, String> {
+    let mut rules: Vec<Rule> = vec![];
+    let mut keys: Vec<&str> = vec![];
+
+    // line number of the section of the current rule
+    let mut section = 0;
+
+    for (number, line) in content.lines().enumerate() {
+        let number = number + 1;
+        let line = line.trim();
+
+        if line.is_empty() || line.starts_with('#') || line.starts_with(';') {
+            continue;
+        }
+
+        if let Some(name) = line.strip_prefix('[') {
+            let name = name
+                .strip_suffix(']')
+                .map(str::trim)
+                .filter(|name| !name.is_empty())
+                .ok_or_else(|| format!("{number}: invalid section: {line}"))?;
+
+            if rules.iter().any(|rule| rule.name == name) {
+                return Err(format!("{number}: duplicate rule: {name}"));
+            }
+
+            if let Some(rule) = rules.last() {
+                check(rule, section)?;
+            }
+
+            rules.push(Rule::new(String::from(name)));
+            keys.clear();
+            section = number;
+
+            continue;
+        }
+
+        let Some(rule) = rules.last_mut() else {
+            return Err(format!("{number}: not within a [rule]: {line}"));
+        };
+
+        let Some((key, value)) = line.split_once('=') else {
+            return Err(format!("{number}: not a key = value pair: {line}"));
+        };
+
+        let (key, value) = (key.trim(), value.trim());
+
+        if keys.contains(&key) {
+            return Err(format!("{number}: duplicate key: {key}"));
+        }
+
+        keys.push(key);
+
+        parse_pair(rule, key, value).map_err(|e| format!("{number}: {e}"))?;
+    }
+
+    if let Some(rule) = rules.last() {
+        check(rule, section)?;
+    }
+
+    Ok(rules)
+}
+
+/// Adds a matcher or setting to a rule.
+fn parse_pair(rule: &mut Rule, key: &str, value: &str) -> Result<(), String> {
+    let settings = &mut rule.settings;
+
+    match key {
+        "comm" => rule.matchers.push(Matcher::Comm(String::from(value))),
+        "exe" => rule.matchers.push(Matcher::Exe(PathBuf::from(value))),
+        "cmdline" => {
+            let regex = Regex::new(value).map_err(|e| format!("{e}"))?;
+            rule.matchers.push(Matcher::Cmdline(regex));
+        }
+        "user" => rule.matchers.push(Matcher::User(parse_user(value)?)),
+        "cgroup" => {
+            if !value.starts_with('/') {
+                return Err(format!(
+                    "cgroup is not an absolute path: {value}"
+                ));
+            }
+
+            rule.matchers.push(Matcher::Cgroup(String::from(value)));
+        }
+
+        "affinity" => {
+            let change = value.parse()?;
+
+            // like other relative values, forked children would get them on
+            // top of the inherited ones
+            if !matches!(change, cpuset::Change::Set(_)) {
+                return Err(format!(
+                    "relative values are not supported: {value}"
+                ));
+            }
+
+            settings.affinity = Some(Affinity {
+                spec: String::from(value),
+                change,
+                requested: None,
+            });
+        }
+        "ioprio" => settings.ioprio = Some(value.parse()?),
+        "nice" => {
+            let nice = parse_absolute(value)?;
+
+            if !(-20..=19).contains(&nice) {
+                return Err(format!(
+                    "not a niceness value between -20 and 19: {nice}"
+                ));
+            }
+
+            settings.nice = Some(nice);
+        }
+        "oom_score_adj" => {
+            let value = parse_absolute(value)?;
+
+            if !(-1000..=1000).contains(&value) {
+                return Err(format!(
+                    "not an oom score adjustment value between -1000 and \
+                     1000: {value}"
+                ));
+            }
+
+            settings.oom_score_adj = Some(value);
+        }
+        "sched" => settings.sched = Some(value.parse()?),
+
+        _ => return Err(format!("unknown key: {key}")),
+    }
+
+    Ok(())
+}
+
+/// Checks that a rule, whose section is at line `number`, is complete.
+fn check(rule: &Rule, number: usize) -> Result<(), String> {
+    if rule.matchers.is_empty() {
+        return Err(format!("{number}: rule {} matches nothing", rule.name));
+    }
+
+    if rule.settings.is_empty() {
+        return Err(format!("{number}: rule {} has no settings", rule.name));
+    }
+
+    Ok(())
+}
+
+/// Parses an absolute value. Relative values are not supported, because
+/// they would add up in forked children, so negative values are absolute.
+fn parse_absolute<T: std::str::FromStr>(value: &str) -> Result<T, String> {
+    if value.starts_with('+') {
+        return Err(format!("relative values are not supported: {value}"));
+    }
+
+    let value = value.strip_prefix('=').unwrap_or(value);
+
+    value
+        .parse()
+        .map_err(|_| format!("not a number: {value:?}"))
+}
+
+/// Parses a user name or ID into a user ID.
+fn parse_user(value: &str) -> Result<u32, String> {
+    if let Ok(uid) = value.parse() {
+        return Ok(uid);
+    }
+
+    let name =
+        CString::new(value).map_err(|_| format!("invalid user: {value:?}"))?;
+
+    let passwd = unsafe { libc::getpwnam(name.as_ptr()) };
+
+    if passwd.is_null() {
+        return Err(format!("unknown user: {value}"));
+    }
+
+    Ok(unsafe { (*passwd).pw_uid })
+}
+
+/// Returns true if a cgroup path is `ancestor` or below it.
+fn is_below(path: &str, ancestor: &str) -> bool {
+    let ancestor = ancestor.trim_end_matches('/');
+
+    path.strip_prefix(ancestor)
+        .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+}
+
+// ----------------------------------------------------------------------------
+// tests
+// ----------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::{is_below, parse};
+
+    #[test]
+    fn rules() {
+        let rules = parse(
+            "# build jobs\n\
+             [make]\n\
+             comm = make\n\
+             cmdline = -j ?[0-9]+\n\
+             nice = 10\n\
+             ioprio = idle\n\
+             \n\
+             [root]\n\
+             user = 0\n\
+             oom_score_adj = -500\n",
+        )
+        .unwrap();
+
+        assert_eq!(rules.len(), 2);
+        assert_eq!(rules[0].name, "make");
+        assert_eq!(rules[0].matchers.len(), 2);
+        assert_eq!(rules[0].settings.nice, Some(10));
+        assert_eq!(rules[1].settings.oom_score_adj, Some(-500));
+        assert_eq!(rules[1].to_string(), "root: user=0 -> oom_score_adj=-500");
+    }
+
+    #[test]
+    fn negative_values() {
+        // unlike the modify commands, a leading - is a negative value
+        for nice in ["-5", "=-5"] {
+            let rules = parse(&format!("[a]\ncomm = x\nnice = {nice}\n"));
+            assert_eq!(rules.unwrap()[0].settings.nice, Some(-5));
+        }
+    }
+
+    #[test]
+    fn errors() {
+        let error = |content| parse(content).unwrap_err();
+
+        assert_eq!(error("comm = x\n"), "1: not within a [rule]: comm = x");
+        assert_eq!(error("[a]\ncomm\n"), "2: not a key = value pair: comm");
+        assert_eq!(error("[a]\nfoo = 1\n"), "2: unknown key: foo");
+        assert_eq!(
+            error("[a]\ncomm = x\ncomm = y\n"),
+            "3: duplicate key: comm"
+        );
+        assert_eq!(
+            error("[a]\ncomm = x\nnice = +5\n"),
+            "3: relative values are not supported: +5"
+        );
+        assert_eq!(
+            error("[a]\ncomm = x\naffinity = -0\n"),
+            "3: relative values are not supported: -0"
+        );
+        assert_eq!(error("[a]\nnice = 5\n"), "1: rule a matches nothing");
+        assert_eq!(
+            error("# x\n[a]\ncomm = x\n[b]\n"),
+            "2: rule a has no settings"
+        );
+        assert!(error("[a]\ncmdline = (\n").starts_with("2: "));
+    }
+
+    #[test]
+    fn cgroup() {
+        assert!(is_below("/user.slice", "/user.slice"));
+        assert!(is_below("/user.slice/user-1000.slice", "/user.slice"));
+        assert!(is_below("/user.slice/user-1000.slice", "/user.slice/"));
+        assert!(!is_below("/user.slice-other", "/user.slice"));
+        assert!(is_below("/system.slice", "/"));
+    }
+}
@@ -0,0 +1,217 @@
+//! Running commands with settings applied from the start.
+
+use std::ffi::OsString;
+use std::os::unix::process::CommandExt;
+use std::process::{Command, ExitCode};
+
+use anyhow::{Context, Result, anyhow};
+use clap::ArgMatches;
+use errno::errno;
+use procfs::process::Process;
+
+use crate::adjust::Adjustment;
+use crate::affinity;
+use crate::cpuset;
+use crate::ioprio::{self, IoPriority};
+use crate::nice;
+use crate::topology;
+use crate::usage::{Summary, Usage};
+
+// ----------------------------------------------------------------------------
+// CLI runner
+// ----------------------------------------------------------------------------
+
+/// Runs `run` subcommand. Returns the exit code of the command if it is run
+/// as a child, otherwise only returns on failure.
+pub fn run(args: &ArgMatches) -> Result<ExitCode> {
+    let mut args_rest = args
+        .get_many::<OsString>("command")
+        .expect("command is a required argument");
+
+    let program = args_rest.next().expect("command has at least one value");
+
+    apply(args)?;
+
+    let mut command = Command::new(program);
+    command.args(args_rest);
+
+    if let Some(format) = args.get_one::<String>("usage") {
+        return run_usage(command, format);
+    }
+
+    // only returns on failure
+    let e = command.exec();
+
+    Err(anyhow!(
+        "executing {} failed: {e}",
+        program.to_string_lossy()
+    ))
+}
+
+/// Runs the command as a child subreaper, reaping all of its descendants,
+/// and prints their aggregated resource usage. Returns the exit code of the
+/// command.
+fn run_usage(mut command: Command, format: &str) -> Result<ExitCode> {
+    // orphaned descendants get reparented to us instead of init
+    if unsafe { libc::prctl(libc::PR_SET_CHILD_SUBREAPER, 1, 0, 0, 0) } == -1 {
+        return Err(anyhow!("prctl PR_SET_CHILD_SUBREAPER: {}", errno()));
+    }
+
+    let child = command.spawn().with_context(|| {
+        format!(
+            "executing {} failed",
+            command.get_program().to_string_lossy()
+        )
+    })?;
+
+    let child = i32::try_from(child.id())?;
+
+    // like time(1), interrupting the command should not lose its usage
+    unsafe {
+        libc::signal(libc::SIGINT, libc::SIG_IGN);
+        libc::signal(libc::SIGQUIT, libc::SIG_IGN);
+    }
+
+    let mut summary = Summary::default();
+    let mut exit_code = 0_u8;
+
+    while let Some(pid) = wait_any()? {
+        // the zombie still has its name and I/O counters, which are gone once
+        // it is reaped; it has to be reaped even if they can not be read
+        let process = Process::new(pid);
+
+        let comm = process
+            .as_ref()
+            .ok()
+            .and_then(|process| process.stat().ok())
+            .map_or_else(|| String::from("?"), |stat| stat.comm);
+
+        let (read_bytes, write_bytes) = process
+            .as_ref()
+            .ok()
+            .and_then(|process| process.io().ok())
+            .map_or((0, 0), |io| (io.read_bytes, io.write_bytes));
+
+        let mut status = 0;
+        let mut rusage = unsafe { std::mem::zeroed() };
+
+        if unsafe { libc::wait4(pid, &raw mut status, 0, &raw mut rusage) }
+            == -1
+        {
+            return Err(anyhow!("wait4: {}", errno()));
+        }
+
+        let usage = Usage::from_rusage(&rusage, read_bytes, write_bytes);
+
+        summary.add(comm, &usage);
+
+        if pid == child {
+            let code = if libc::WIFSIGNALED(status) {
+                128 + libc::WTERMSIG(status)
+            } else {
+                libc::WEXITSTATUS(status)
+            };
+
+            exit_code = u8::try_from(code).unwrap_or(u8::MAX);
+        }
+    }
+
+    match format {
+        "json" => eprintln!("{}", summary.to_json()),
+        _ => eprint!("{}", summary.to_text()),
+    }
+
+    Ok(ExitCode::from(exit_code))
+}
+
+/// Waits for any child to exit without reaping it. Returns `None` if there
+/// are no children left.
+fn wait_any() -> Result<Option<i32>> {
+    let mut info: libc::siginfo_t = unsafe { std::mem::zeroed() };
+
+    let result = unsafe {
+        libc::waitid(
+            libc::P_ALL,
+            0,
+            &raw mut info,
+            libc::WEXITED | libc::WNOWAIT,
+        )
+    };
+
+    if result == -1 {
+        let e = errno();
+
+        return match e.0 {
+            libc::ECHILD => Ok(None),
+            libc::EINTR => wait_any(),
+            _ => Err(anyhow!("waitid: {e}")),
+        };
+    }
+
+    Ok(Some(unsafe { info.si_pid() }))
+}
+
+// ----------------------------------------------------------------------------
+// helper
+// ----------------------------------------------------------------------------
+
+/// Applies the settings to this process, which the command and all of its
+/// descendants inherit.
+fn apply(args: &ArgMatches) -> Result<()> {
+    let verbose = args.get_flag("verbose");
+
+    let process = Process::myself()?;
+    let pid = process.pid;
+
+    if let Some(adjustment) = args.get_one::<Adjustment<i32>>("niceness") {
+        let pid = u32::try_from(pid)?;
+
+        let before = nice::get(pid)?;
+        let after = adjustment.apply(before, -20, 19);
+
+        if verbose {
+            eprintln!("nice: {before} -> {after}");
+        }
+
+        nice::set(pid, after)?;
+    }
+
+    if let Some(&ioprio) = args.get_one::<IoPriority>("ioprio") {
+        if verbose {
+            eprintln!("ioprio: {} -> {ioprio}", ioprio::get(pid)?);
+        }
+
+        ioprio::set(pid, ioprio)?;
+    }
+
+    if let Some(adjustment) = args.get_one::<Adjustment<i16>>("oom_score_adj")
+    {
+        let before = process.oom_score_adj()?;
+        let after = adjustment.apply(before, -1000, 1000);
+
+        if verbose {
+            eprintln!("oom_score_adj: {before} -> {after}");
+        }
+
+        process.set_oom_score_adj(after)?;
+    }
+
+    if let Some(change) = args.get_one::<cpuset::Change>("cpuset") {
+        let online = topology::online()?;
+        let requested = affinity::requested(change)?;
+
+        let before = affinity::get(pid)?;
+        let after =
+            affinity::change(&process, change, requested.as_deref(), &online)?;
+
+        if verbose {
+            eprintln!(
+                "affinity: {} -> {}",
+                cpuset::format_list(&before),
+                cpuset::format_list(&after)
+            );
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,450 @@
+//! Getting and setting scheduling policy and realtime priority, as well as
+//! extended scheduling attributes like utilization clamping.
+
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+
+use anyhow::{Result, anyhow};
+use errno::{Errno, errno};
+
+// These are not part of libc, see linux/sched/types.h and linux/sched.h.
+const SCHED_ATTR_SIZE_VER1: u32 = 56;
+const SCHED_FLAG_RESET_ON_FORK: u64 = 0x01;
+const SCHED_FLAG_KEEP_POLICY: u64 = 0x08;
+const SCHED_FLAG_KEEP_PARAMS: u64 = 0x10;
+const SCHED_FLAG_UTIL_CLAMP_MIN: u64 = 0x20;
+const SCHED_FLAG_UTIL_CLAMP_MAX: u64 = 0x40;
+
+/// Maximum utilization, i.e. a fully used CPU.
+pub const UTIL_MAX: u32 = 1024;
+
+/// A scheduling policy.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Policy {
+    Other,
+    Batch,
+    Idle,
+    Fifo,
+    RoundRobin,
+    Deadline,
+}
+
+impl Policy {
+    const fn from_raw(policy: libc::c_int) -> Option<Self> {
+        match policy {
+            libc::SCHED_OTHER => Some(Self::Other),
+            libc::SCHED_BATCH => Some(Self::Batch),
+            libc::SCHED_IDLE => Some(Self::Idle),
+            libc::SCHED_FIFO => Some(Self::Fifo),
+            libc::SCHED_RR => Some(Self::RoundRobin),
+            libc::SCHED_DEADLINE => Some(Self::Deadline),
+            _ => None,
+        }
+    }
+
+    const fn raw(self) -> libc::c_int {
+        match self {
+            Self::Other => libc::SCHED_OTHER,
+            Self::Batch => libc::SCHED_BATCH,
+            Self::Idle => libc::SCHED_IDLE,
+            Self::Fifo => libc::SCHED_FIFO,
+            Self::RoundRobin => libc::SCHED_RR,
+            Self::Deadline => libc::SCHED_DEADLINE,
+        }
+    }
+
+    /// Returns true for the realtime policies that have a priority.
+    pub const fn is_realtime(self) -> bool {
+        matches!(self, Self::Fifo | Self::RoundRobin)
+    }
+}
+
+impl fmt::Display for Policy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::Other => "other",
+            Self::Batch => "batch",
+            Self::Idle => "idle",
+            Self::Fifo => "fifo",
+            Self::RoundRobin => "rr",
+            Self::Deadline => "deadline",
+        };
+
+        f.write_str(name)
+    }
+}
+
+impl FromStr for Policy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "other" | "normal" => Ok(Self::Other),
+            "batch" => Ok(Self::Batch),
+            "idle" => Ok(Self::Idle),
+            "fifo" => Ok(Self::Fifo),
+            "rr" => Ok(Self::RoundRobin),
+            "deadline" => Err(String::from(
+                "deadline scheduling needs runtime parameters and is not \
+                 supported",
+            )),
+            _ => Err(format!("unknown scheduling policy: {s:?}")),
+        }
+    }
+}
+
+/// A scheduling policy with its realtime priority, written like `fifo:50`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Scheduling {
+    pub policy: Policy,
+    pub priority: i32,
+}
+
+impl fmt::Display for Scheduling {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.policy.is_realtime() {
+            write!(f, "{}:{}", self.policy, self.priority)
+        } else {
+            write!(f, "{}", self.policy)
+        }
+    }
+}
+
+impl FromStr for Scheduling {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (policy, priority) = match s.split_once(':') {
+            Some((policy, priority)) => (policy, Some(priority)),
+            None => (s, None),
+        };
+
+        let policy = policy.parse::<Policy>()?;
+
+        let priority = match priority {
+            Some(priority) => priority
+                .parse::<i32>()
+                .map_err(|e| format!("{priority:?}: {e}"))?,
+            None if policy.is_realtime() => {
+                return Err(format!(
+                    "{policy} needs a priority from 1 to 99, e.g. {policy}:50"
+                ));
+            }
+            None => 0,
+        };
+
+        if policy.is_realtime() && !(1..=99).contains(&priority) {
+            Err(format!("not a realtime priority from 1 to 99: {priority}"))
+        } else if !policy.is_realtime() && priority != 0 {
+            Err(format!("{policy} has no priority"))
+        } else {
+            Ok(Self { policy, priority })
+        }
+    }
+}
+
+/// Returns the scheduling policy and priority of the given task.
+pub fn get(pid: libc::pid_t) -> Result<Scheduling> {
+    let policy = unsafe { libc::sched_getscheduler(pid) };
+
+    if policy == -1 {
+        return Err(anyhow!("sched_getscheduler: {}", errno()));
+    }
+
+    // a flag that makes children start with the default policy again
+    let policy = policy & !libc::SCHED_RESET_ON_FORK;
+
+    let policy = Policy::from_raw(policy)
+        .ok_or_else(|| anyhow!("unknown scheduling policy: {policy}"))?;
+
+    let mut param = libc::sched_param { sched_priority: 0 };
+
+    if 0 != unsafe { libc::sched_getparam(pid, &raw mut param) } {
+        return Err(anyhow!("sched_getparam: {}", errno()));
+    }
+
+    Ok(Scheduling {
+        policy,
+        priority: param.sched_priority,
+    })
+}
+
+/// Sets the scheduling policy and priority of the given task.
+pub fn set(pid: libc::pid_t, scheduling: Scheduling) -> Result<()> {
+    let param = libc::sched_param {
+        sched_priority: scheduling.priority,
+    };
+
+    let result = unsafe {
+        libc::sched_setscheduler(
+            pid,
+            scheduling.policy.raw(),
+            &raw const param,
+        )
+    };
+
+    if result == -1 {
+        return Err(anyhow!("for pid {pid} sched_setscheduler: {}", errno()));
+    }
+
+    Ok(())
+}
+
+// ----------------------------------------------------------------------------
+// extended attributes
+// ----------------------------------------------------------------------------
+
+/// Raw `struct sched_attr` of `sched_getattr(2)` and `sched_setattr(2)`.
+#[repr(C)]
+#[derive(Debug, Default)]
+struct SchedAttr {
+    size: u32,
+    sched_policy: u32,
+    sched_flags: u64,
+    sched_nice: i32,
+    sched_priority: u32,
+    sched_runtime: u64,
+    sched_deadline: u64,
+    sched_period: u64,
+    sched_util_min: u32,
+    sched_util_max: u32,
+}
+
+/// Extended scheduling attributes of a task.
+#[derive(Debug)]
+pub struct Attributes {
+    pub policy: Policy,
+    pub nice: i32,
+    pub priority: u32,
+
+    /// Runtime, deadline and period in nanoseconds of deadline tasks.
+    pub deadline: Option<(u64, u64, u64)>,
+
+    /// Time slice in nanoseconds of fair tasks, if the kernel reports it.
+    pub slice: Option<u64>,
+
+    /// Minimum and maximum utilization, if the kernel supports clamping.
+    pub uclamp: Option<(u32, u32)>,
+}
+
+impl fmt::Display for Attributes {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.policy)?;
+
+        if self.policy.is_realtime() {
+            write!(f, ":{}", self.priority)?;
+        } else if self.policy != Policy::Deadline {
+            write!(f, " nice {}", self.nice)?;
+        }
+
+        if let Some((runtime, deadline, period)) = self.deadline {
+            write!(
+                f,
+                " runtime {runtime}ns deadline {deadline}ns period {period}ns"
+            )?;
+        }
+
+        if let Some(slice) = self.slice {
+            write!(f, " slice {slice}ns")?;
+        }
+
+        match self.uclamp {
+            Some((min, max)) => write!(f, " uclamp {min}-{max}"),
+            None => write!(f, " uclamp unsupported"),
+        }
+    }
+}
+
+/// Returns the extended scheduling attributes of the given task.
+pub fn get_attr(pid: libc::pid_t) -> Result<Attributes> {
+    let attr = read_attr(pid)?;
+
+    let policy = libc::c_int::try_from(attr.sched_policy)
+        .ok()
+        .and_then(Policy::from_raw)
+        .ok_or_else(|| {
+            anyhow!("unknown scheduling policy: {}", attr.sched_policy)
+        })?;
+
+    let deadline = (policy == Policy::Deadline).then_some((
+        attr.sched_runtime,
+        attr.sched_deadline,
+        attr.sched_period,
+    ));
+
+    let slice = (matches!(policy, Policy::Other | Policy::Batch)
+        && attr.sched_runtime != 0)
+        .then_some(attr.sched_runtime);
+
+    // the kernel reports zeros without clamping support
+    let uclamp = uclamp_supported()
+        .then_some((attr.sched_util_min, attr.sched_util_max));
+
+    Ok(Attributes {
+        policy,
+        nice: attr.sched_nice,
+        priority: attr.sched_priority,
+        deadline,
+        slice,
+        uclamp,
+    })
+}
+
+/// Sets the minimum and/or maximum utilization clamp of the given task,
+/// keeping its policy and parameters.
+pub fn set_uclamp(
+    pid: libc::pid_t,
+    min: Option<u32>,
+    max: Option<u32>,
+) -> Result<()> {
+    // Like uclampset(1), pass the current policy, nice value, priority and
+    // deadline parameters on. The kernel validates them despite the keep
+    // flags, e.g. zeros fail for realtime tasks and lowering the nice value
+    // of niced tasks needs privileges.
+    let mut attr = read_attr(pid)?;
+
+    attr.size = SCHED_ATTR_SIZE_VER1;
+    attr.sched_flags = (attr.sched_flags & SCHED_FLAG_RESET_ON_FORK)
+        | SCHED_FLAG_KEEP_POLICY
+        | SCHED_FLAG_KEEP_PARAMS;
+
+    if let Some(min) = min {
+        attr.sched_flags |= SCHED_FLAG_UTIL_CLAMP_MIN;
+        attr.sched_util_min = min;
+    }
+
+    if let Some(max) = max {
+        attr.sched_flags |= SCHED_FLAG_UTIL_CLAMP_MAX;
+        attr.sched_util_max = max;
+    }
+
+    let result = unsafe {
+        libc::syscall(libc::SYS_sched_setattr, pid, &raw const attr, 0)
+    };
+
+    if result == -1 {
+        return Err(attr_error("sched_setattr", pid, errno()));
+    }
+
+    Ok(())
+}
+
+/// Returns the raw extended scheduling attributes of the given task.
+fn read_attr(pid: libc::pid_t) -> Result<SchedAttr> {
+    let mut attr = SchedAttr::default();
+
+    let result = unsafe {
+        libc::syscall(
+            libc::SYS_sched_getattr,
+            pid,
+            &raw mut attr,
+            SCHED_ATTR_SIZE_VER1,
+            0,
+        )
+    };
+
+    if result == -1 {
+        return Err(attr_error("sched_getattr", pid, errno()));
+    }
+
+    Ok(attr)
+}
+
+/// Returns true if the kernel supports utilization clamping, which comes
+/// with its sysctls (Linux 5.3+ with `CONFIG_UCLAMP_TASK`).
+fn uclamp_supported() -> bool {
+    Path::new("/proc/sys/kernel/sched_util_clamp_max").exists()
+}
+
+/// Explains errors that mean the kernel lacks support.
+fn attr_error(syscall: &str, pid: libc::pid_t, err: Errno) -> anyhow::Error {
+    match err.0 {
+        libc::ENOSYS => {
+            anyhow!("{syscall} is not supported by this kernel (Linux 3.14+)")
+        }
+
+        libc::E2BIG => anyhow!(
+            "for pid {pid} {syscall}: utilization clamping is not supported \
+             by this kernel (Linux 5.3+)"
+        ),
+
+        libc::EOPNOTSUPP => anyhow!(
+            "for pid {pid} {syscall}: utilization clamping is not enabled in \
+             this kernel (CONFIG_UCLAMP_TASK)"
+        ),
+
+        _ => anyhow!("for pid {pid} {syscall}: {err}"),
+    }
+}
+
+// ----------------------------------------------------------------------------
+// tests
+// ----------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use std::process::{Child, Command};
+
+    use super::{
+        Attributes, Policy, Scheduling, get_attr, set, set_uclamp,
+        uclamp_supported,
+    };
+
+    /// Clamps the maximum utilization of a child and returns its
+    /// attributes afterwards.
+    fn clamp(child: &Child) -> Attributes {
+        let pid = libc::pid_t::try_from(child.id()).unwrap();
+        let result = set_uclamp(pid, None, Some(512));
+
+        if uclamp_supported() {
+            assert!(result.is_ok(), "{result:?}");
+        } else {
+            // passing the parameters on gets past the kernel checking them
+            let e = result.unwrap_err().to_string();
+            assert!(e.contains("utilization clamping"), "{e}");
+        }
+
+        get_attr(pid).unwrap()
+    }
+
+    #[test]
+    fn uclamp_of_niced_task() {
+        let mut child = Command::new("sleep").arg("10").spawn().unwrap();
+
+        crate::nice::set(child.id(), 10).unwrap();
+
+        let attr = clamp(&child);
+        assert_eq!(attr.nice, 10);
+
+        if uclamp_supported() {
+            assert_eq!(attr.uclamp.map(|(_, max)| max), Some(512));
+        } else {
+            assert_eq!(attr.uclamp, None);
+        }
+
+        child.kill().unwrap();
+        child.wait().unwrap();
+    }
+
+    #[test]
+    fn uclamp_of_realtime_task() {
+        let mut child = Command::new("sleep").arg("10").spawn().unwrap();
+        let pid = libc::pid_t::try_from(child.id()).unwrap();
+
+        let fifo = Scheduling {
+            policy: Policy::Fifo,
+            priority: 1,
+        };
+
+        // needs CAP_SYS_NICE or an RLIMIT_RTPRIO
+        if set(pid, fifo).is_ok() {
+            let attr = clamp(&child);
+            assert_eq!(attr.policy, Policy::Fifo);
+            assert_eq!(attr.priority, 1);
+        }
+
+        child.kill().unwrap();
+        child.wait().unwrap();
+    }
+}
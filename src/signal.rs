@@ -0,0 +1,115 @@
+//! Parsing and sending signals.
+
+use std::fmt;
+use std::str::FromStr;
+
+use anyhow::{Result, anyhow};
+use errno::errno;
+
+/// Highest signal number, including the realtime signals.
+const MAX_SIGNAL: libc::c_int = 64;
+
+/// Signal names without their `SIG` prefix.
+const NAMES: [(&str, libc::c_int); 30] = [
+    ("HUP", libc::SIGHUP),
+    ("INT", libc::SIGINT),
+    ("QUIT", libc::SIGQUIT),
+    ("ILL", libc::SIGILL),
+    ("TRAP", libc::SIGTRAP),
+    ("ABRT", libc::SIGABRT),
+    ("BUS", libc::SIGBUS),
+    ("FPE", libc::SIGFPE),
+    ("KILL", libc::SIGKILL),
+    ("USR1", libc::SIGUSR1),
+    ("SEGV", libc::SIGSEGV),
+    ("USR2", libc::SIGUSR2),
+    ("PIPE", libc::SIGPIPE),
+    ("ALRM", libc::SIGALRM),
+    ("TERM", libc::SIGTERM),
+    ("CHLD", libc::SIGCHLD),
+    ("CONT", libc::SIGCONT),
+    ("STOP", libc::SIGSTOP),
+    ("TSTP", libc::SIGTSTP),
+    ("TTIN", libc::SIGTTIN),
+    ("TTOU", libc::SIGTTOU),
+    ("URG", libc::SIGURG),
+    ("XCPU", libc::SIGXCPU),
+    ("XFSZ", libc::SIGXFSZ),
+    ("VTALRM", libc::SIGVTALRM),
+    ("PROF", libc::SIGPROF),
+    ("WINCH", libc::SIGWINCH),
+    ("IO", libc::SIGIO),
+    ("PWR", libc::SIGPWR),
+    ("SYS", libc::SIGSYS),
+];
+
+/// A signal, written like `TERM`, `SIGTERM` or `15`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Signal(pub libc::c_int);
+
+impl fmt::Display for Signal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match NAMES.iter().find(|(_, signal)| *signal == self.0) {
+            Some((name, _)) => write!(f, "SIG{name}"),
+            None => write!(f, "signal {}", self.0),
+        }
+    }
+}
+
+impl FromStr for Signal {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Ok(signal) = s.parse::<libc::c_int>() {
+            return if (1..=MAX_SIGNAL).contains(&signal) {
+                Ok(Self(signal))
+            } else {
+                Err(format!("not a signal number from 1 to 64: {signal}"))
+            };
+        }
+
+        let name = s.to_ascii_uppercase();
+        let name = name.strip_prefix("SIG").unwrap_or(&name);
+
+        NAMES
+            .iter()
+            .find(|(candidate, _)| *candidate == name)
+            .map(|(_, signal)| Self(*signal))
+            .ok_or_else(|| format!("unknown signal: {s:?}"))
+    }
+}
+
+/// Sends a signal to the given process.
+pub fn send(pid: libc::pid_t, signal: Signal) -> Result<()> {
+    if unsafe { libc::kill(pid, signal.0) } == -1 {
+        return Err(anyhow!("for pid {pid} kill {signal}: {}", errno()));
+    }
+
+    Ok(())
+}
+
+// ----------------------------------------------------------------------------
+// tests
+// ----------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::Signal;
+
+    #[test]
+    fn parse() {
+        assert_eq!("TERM".parse(), Ok(Signal(libc::SIGTERM)));
+        assert_eq!("sigkill".parse(), Ok(Signal(libc::SIGKILL)));
+        assert_eq!("18".parse(), Ok(Signal(18)));
+
+        assert!("0".parse::<Signal>().is_err());
+        assert!("65".parse::<Signal>().is_err());
+        assert!("SIGFOO".parse::<Signal>().is_err());
+    }
+
+    #[test]
+    fn display() {
+        assert_eq!(Signal(libc::SIGTERM).to_string(), "SIGTERM");
+        assert_eq!(Signal(40).to_string(), "signal 40");
+    }
+}
@@ -0,0 +1,676 @@
+//! Reading the CPU topology from sysfs.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use anyhow::{Context, Result, anyhow};
+use clap::ArgMatches;
+use termtree::Tree;
+
+use crate::cpuset::{self, Domain, Item, Smt, Spec};
+
+const SYS_CPU: &str = "/sys/devices/system/cpu";
+const SYS_NODE: &str = "/sys/devices/system/node";
+
+// ----------------------------------------------------------------------------
+// CLI runner
+// ----------------------------------------------------------------------------
+
+/// Runs `topology` subcommand.
+pub fn run(_args: &ArgMatches) -> Result<()> {
+    let topology = Topology::read()?;
+
+    let list = |cpus: &[usize]| {
+        if cpus.is_empty() {
+            String::from("none")
+        } else {
+            cpuset::format_list(cpus)
+        }
+    };
+
+    println!("online {}", list(&topology.online()));
+    println!("offline {}", list(&read_optional_list("offline")?));
+    println!("isolated {}", list(&read_optional_list("isolated")?));
+    println!("nohz_full {}", list(&read_optional_list("nohz_full")?));
+
+    let core_types = read_core_types()?;
+
+    for (name, cpus) in &core_types {
+        println!("{name} {}", list(cpus));
+    }
+
+    let mut sockets: Vec<usize> =
+        topology.cpus.iter().map(|cpu| cpu.socket).collect();
+    sockets.sort_unstable();
+    sockets.dedup();
+
+    for socket in sockets {
+        let cpus = topology.domain(|cpu| cpu.socket == socket);
+
+        let mut tree =
+            Tree::new(format!("socket:{socket} cpus {}", list(&cpus)));
+
+        for node in topology
+            .ids(|cpu| (cpu.socket == socket).then_some(cpu.node).flatten())
+        {
+            let cpus = topology.domain(|cpu| cpu.node == Some(node));
+            tree.push(format!("node:{node} cpus {}", list(&cpus)));
+        }
+
+        for l3 in topology
+            .ids(|cpu| (cpu.socket == socket).then_some(cpu.l3).flatten())
+        {
+            let cpus = topology.domain(|cpu| cpu.l3 == Some(l3));
+
+            let size = read_caches(cpus[0])?
+                .into_iter()
+                .find(|cache| cache.level == 3 && cache.kind != "Instruction")
+                .map(|cache| cache.size)
+                .unwrap_or_default();
+
+            tree.push(format!("l3:{l3} cpus {} L3 {size}", list(&cpus)));
+        }
+
+        for core in
+            topology.ids(|cpu| (cpu.socket == socket).then_some(cpu.core))
+        {
+            let cpus = topology.domain(|cpu| cpu.core == core);
+
+            let mut line = vec![format!("core:{core} cpus {}", list(&cpus))];
+
+            // the last level cache is already shown by its domain
+            for cache in read_caches(cpus[0])? {
+                if cache.level >= 3 {
+                    continue;
+                }
+
+                if cache.shared == cpus {
+                    line.push(format!("{} {}", cache.name(), cache.size));
+                } else {
+                    line.push(format!(
+                        "{} {} shared {}",
+                        cache.name(),
+                        cache.size,
+                        list(&cache.shared)
+                    ));
+                }
+            }
+
+            for (name, _) in core_types
+                .iter()
+                .filter(|(_, types)| types.contains(&cpus[0]))
+            {
+                line.push(name.clone());
+            }
+
+            tree.push(line.join(" "));
+        }
+
+        println!("{tree}");
+    }
+
+    Ok(())
+}
+
+// ----------------------------------------------------------------------------
+// topology data structure
+// ----------------------------------------------------------------------------
+
+/// The topology of a single online CPU.
+#[derive(Debug)]
+pub struct Cpu {
+    /// The CPU number.
+    pub id: usize,
+
+    /// The socket aka physical package identifier.
+    pub socket: usize,
+
+    /// The NUMA node, if the kernel has NUMA support.
+    pub node: Option<usize>,
+
+    /// The index of the physical core, counted machine-wide in the order of
+    /// the lowest CPU of each core.
+    pub core: usize,
+
+    /// The position of this CPU among the SMT siblings of its core.
+    pub thread: usize,
+
+    /// The index of the L3 cache domain, counted like cores.
+    pub l3: Option<usize>,
+}
+
+/// Orders in which CPUs are handed out to tasks.
+#[derive(Clone, Copy, Debug)]
+pub enum Placement {
+    /// Fills all SMT siblings of a core, then the next core of the same
+    /// socket.
+    Compact,
+
+    /// Spreads over sockets first, then cores, then SMT siblings.
+    Scatter,
+
+    /// Plain CPU number order.
+    RoundRobin,
+}
+
+/// The topology of all online CPUs.
+#[derive(Debug)]
+pub struct Topology {
+    pub cpus: Vec<Cpu>,
+}
+
+impl Topology {
+    /// Reads the topology of all online CPUs from sysfs.
+    pub fn read() -> Result<Self> {
+        let online = online()?;
+        let nodes = read_nodes()?;
+
+        let mut cores = Indexer::default();
+        let mut l3s = Indexer::default();
+
+        let mut cpus = vec![];
+
+        for id in online {
+            let topology = format!("{SYS_CPU}/cpu{id}/topology");
+
+            let socket: i64 =
+                fs::read_to_string(format!("{topology}/physical_package_id"))
+                    .with_context(|| {
+                        format!("reading socket of CPU {id} failed")
+                    })?
+                    .trim()
+                    .parse()
+                    .with_context(|| {
+                        format!("parsing socket of CPU {id} failed")
+                    })?;
+
+            // some platforms, e.g. virtual machines or ARM without socket
+            // information, report -1
+            let socket = usize::try_from(socket).unwrap_or(0);
+
+            // core_cpus_list is the newer name of thread_siblings_list
+            let siblings = read_list(format!("{topology}/core_cpus_list"))
+                .or_else(|_| {
+                    read_list(format!("{topology}/thread_siblings_list"))
+                })?;
+
+            let thread = siblings
+                .iter()
+                .position(|&cpu| cpu == id)
+                .ok_or_else(|| {
+                    anyhow!("CPU {id} is missing from its own core siblings")
+                })?;
+
+            let l3 = read_cache_list(id, 3)?.map(|shared| l3s.index(shared));
+
+            let node = nodes
+                .iter()
+                .find(|(_, cpus)| cpus.contains(&id))
+                .map(|(node, _)| *node);
+
+            cpus.push(Cpu {
+                id,
+                socket,
+                node,
+                core: cores.index(siblings),
+                thread,
+                l3,
+            });
+        }
+
+        cores.renumber(&mut cpus, |cpu| Some(&mut cpu.core));
+        l3s.renumber(&mut cpus, |cpu| cpu.l3.as_mut());
+
+        Ok(Self { cpus })
+    }
+
+    /// Returns all online CPUs.
+    pub fn online(&self) -> Vec<usize> {
+        self.cpus.iter().map(|cpu| cpu.id).collect()
+    }
+
+    /// Returns the sorted identifiers the given function extracts from CPUs.
+    fn ids<F>(&self, f: F) -> Vec<usize>
+    where
+        F: Fn(&Cpu) -> Option<usize>,
+    {
+        let mut ids: Vec<usize> = self.cpus.iter().filter_map(f).collect();
+        ids.sort_unstable();
+        ids.dedup();
+        ids
+    }
+
+    /// Returns the CPUs matching the predicate.
+    fn domain<P>(&self, predicate: P) -> Vec<usize>
+    where
+        P: Fn(&Cpu) -> bool,
+    {
+        self.cpus
+            .iter()
+            .filter(|cpu| predicate(cpu))
+            .map(|cpu| cpu.id)
+            .collect()
+    }
+
+    /// Returns the NUMA node of the given CPU, if it is online and the kernel
+    /// has NUMA support.
+    pub fn node(&self, id: usize) -> Option<usize> {
+        self.cpu(id).and_then(|cpu| cpu.node)
+    }
+
+    /// Returns the online CPUs of the given NUMA node.
+    pub fn node_cpus(&self, node: usize) -> Vec<usize> {
+        self.domain(|cpu| cpu.node == Some(node))
+    }
+
+    /// Returns the topology of the given CPU, if it is online.
+    pub fn cpu(&self, id: usize) -> Option<&Cpu> {
+        self.cpus.iter().find(|cpu| cpu.id == id)
+    }
+
+    /// Returns the given CPUs in the order of the placement strategy. CPUs
+    /// that are not online are moved to the end.
+    pub fn order(&self, cpus: &[usize], placement: Placement) -> Vec<usize> {
+        let mut online: Vec<&Cpu> = self
+            .cpus
+            .iter()
+            .filter(|cpu| cpus.contains(&cpu.id))
+            .collect();
+
+        match placement {
+            Placement::Compact => {
+                online.sort_by_key(|cpu| (cpu.socket, cpu.core, cpu.thread));
+            }
+
+            Placement::Scatter => {
+                // rank of each core within its socket, so sockets alternate
+                let rank = |cpu: &Cpu| {
+                    let mut cores: Vec<usize> = self
+                        .cpus
+                        .iter()
+                        .filter(|other| {
+                            other.socket == cpu.socket && other.core < cpu.core
+                        })
+                        .map(|other| other.core)
+                        .collect();
+
+                    cores.sort_unstable();
+                    cores.dedup();
+                    cores.len()
+                };
+
+                online.sort_by_cached_key(|cpu| {
+                    (cpu.thread, rank(cpu), cpu.socket)
+                });
+            }
+
+            Placement::RoundRobin => online.sort_by_key(|cpu| cpu.id),
+        }
+
+        let offline = cpus.iter().filter(|&&id| self.cpu(id).is_none());
+
+        online
+            .iter()
+            .map(|cpu| cpu.id)
+            .chain(offline.copied())
+            .collect()
+    }
+
+    /// Resolves a cpuset specification into a sorted list of CPUs.
+    ///
+    /// Explicit CPU numbers are taken as they are, even if they are not
+    /// online. When they are applied to a task, those that are offline or
+    /// not permitted by its cgroup are dropped with a warning, see
+    /// [`affinity::set_permitted`](crate::affinity::set_permitted).
+    pub fn resolve(&self, spec: &Spec) -> Result<Vec<usize>> {
+        let (items, smt) = match spec {
+            Spec::Free => return Ok(self.online()),
+            Spec::Items(items, smt) => (items, smt),
+        };
+
+        let mut resolved = if items.is_empty() {
+            self.online()
+        } else {
+            let mut resolved = vec![];
+
+            for item in items {
+                match item {
+                    Item::Cpus(cpus) => resolved.extend(cpus),
+                    Item::Select(domain, ids) => {
+                        for &id in ids {
+                            resolved.extend(self.select(*domain, id)?);
+                        }
+                    }
+                }
+            }
+
+            resolved.sort_unstable();
+            resolved.dedup();
+            resolved
+        };
+
+        if let Some(smt) = smt {
+            resolved.retain(|&id| self.is_sibling(id, *smt));
+        }
+
+        Ok(resolved)
+    }
+
+    /// Returns the CPUs of a single topology domain.
+    fn select(&self, domain: Domain, id: usize) -> Result<Vec<usize>> {
+        let cpus: Vec<usize> = self
+            .cpus
+            .iter()
+            .filter(|cpu| match domain {
+                Domain::Node => cpu.node == Some(id),
+                Domain::Socket => cpu.socket == id,
+                Domain::Core => cpu.core == id,
+                Domain::L3 => cpu.l3 == Some(id),
+            })
+            .map(|cpu| cpu.id)
+            .collect();
+
+        if cpus.is_empty() {
+            Err(anyhow!("no online CPUs in {}:{id}", domain.keyword()))
+        } else {
+            Ok(cpus)
+        }
+    }
+
+    /// Returns true if the given CPU is the selected sibling of its core.
+    /// CPUs that are not online are not siblings of anything.
+    fn is_sibling(&self, id: usize, smt: Smt) -> bool {
+        let Some(cpu) = self.cpu(id) else {
+            return false;
+        };
+
+        match smt {
+            Smt::First => cpu.thread == 0,
+            Smt::Nth(n) => cpu.thread == n,
+            Smt::Last => !self.cpus.iter().any(|other| {
+                other.core == cpu.core && other.thread > cpu.thread
+            }),
+        }
+    }
+}
+
+// ----------------------------------------------------------------------------
+// helper
+// ----------------------------------------------------------------------------
+
+/// Assigns indices to domains identified by the CPUs they contain.
+#[derive(Default)]
+struct Indexer {
+    domains: Vec<Vec<usize>>,
+}
+
+impl Indexer {
+    /// Returns the preliminary index of the domain containing `cpus`.
+    fn index(&mut self, cpus: Vec<usize>) -> usize {
+        self.domains
+            .iter()
+            .position(|domain| *domain == cpus)
+            .unwrap_or_else(|| {
+                self.domains.push(cpus);
+                self.domains.len() - 1
+            })
+    }
+
+    /// Renumbers preliminary indices in the order of the lowest CPU of each
+    /// domain, so identifiers do not depend on the order of discovery.
+    fn renumber<F>(&self, cpus: &mut [Cpu], field: F)
+    where
+        F: Fn(&mut Cpu) -> Option<&mut usize>,
+    {
+        let mut order: Vec<usize> = (0..self.domains.len()).collect();
+        order.sort_by_key(|&i| self.domains[i].first().copied());
+
+        let mut renumbered = vec![0; order.len()];
+        for (new, old) in order.into_iter().enumerate() {
+            renumbered[old] = new;
+        }
+
+        for cpu in cpus {
+            if let Some(index) = field(cpu) {
+                *index = renumbered[*index];
+            }
+        }
+    }
+}
+
+/// Returns all online CPUs, without reading the rest of the topology.
+pub fn online() -> Result<Vec<usize>> {
+    read_list(format!("{SYS_CPU}/online"))
+}
+
+/// Resolves a cpuset like [`Topology::resolve`], but reads the topology only
+/// if the cpuset selects CPUs by it.
+pub fn resolve(spec: &Spec) -> Result<Vec<usize>> {
+    match spec {
+        _ if spec.has_selectors() => Topology::read()?.resolve(spec),
+        Spec::Items(items, _) if !items.is_empty() => {
+            let mut cpus: Vec<usize> = items
+                .iter()
+                .flat_map(|item| match item {
+                    Item::Cpus(cpus) => cpus.as_slice(),
+                    Item::Select(..) => &[],
+                })
+                .copied()
+                .collect();
+
+            cpus.sort_unstable();
+            cpus.dedup();
+
+            Ok(cpus)
+        }
+        _ => online(),
+    }
+}
+
+/// Reads a cpulist file, like `/sys/devices/system/cpu/online`.
+pub fn read_list(path: impl AsRef<Path>) -> Result<Vec<usize>> {
+    let path = path.as_ref();
+
+    let content = fs::read_to_string(path)
+        .with_context(|| format!("reading {} failed", path.display()))?;
+
+    if content.trim().is_empty() {
+        Ok(vec![])
+    } else {
+        cpuset::parse_list(&content).map_err(|e| anyhow!(e))
+    }
+}
+
+/// Returns the CPUs of each NUMA node, empty if the kernel has no NUMA
+/// support.
+fn read_nodes() -> Result<BTreeMap<usize, Vec<usize>>> {
+    let mut nodes = BTreeMap::new();
+
+    let Ok(entries) = fs::read_dir(SYS_NODE) else {
+        return Ok(nodes);
+    };
+
+    for entry in entries {
+        let entry = entry?;
+        let name = entry.file_name();
+
+        let Some(node) = name
+            .to_str()
+            .and_then(|name| name.strip_prefix("node"))
+            .and_then(|node| node.parse::<usize>().ok())
+        else {
+            continue;
+        };
+
+        nodes.insert(node, read_list(entry.path().join("cpulist"))?);
+    }
+
+    Ok(nodes)
+}
+
+/// Returns the CPUs sharing the cache of the given level with `cpu`, if the
+/// CPU has a unified or data cache of that level.
+fn read_cache_list(cpu: usize, level: u8) -> Result<Option<Vec<usize>>> {
+    Ok(read_caches(cpu)?
+        .into_iter()
+        .find(|cache| cache.level == level && cache.kind != "Instruction")
+        .map(|cache| cache.shared))
+}
+
+/// Returns the caches of the given CPU.
+fn read_caches(cpu: usize) -> Result<Vec<Cache>> {
+    let Ok(entries) = fs::read_dir(format!("{SYS_CPU}/cpu{cpu}/cache")) else {
+        return Ok(vec![]);
+    };
+
+    let mut caches = vec![];
+
+    for entry in entries {
+        let path = entry?.path();
+
+        let Ok(level) = fs::read_to_string(path.join("level")) else {
+            continue;
+        };
+
+        let read = |name| {
+            fs::read_to_string(path.join(name))
+                .map(|value| value.trim().to_owned())
+                .unwrap_or_default()
+        };
+
+        caches.push(Cache {
+            level: level.trim().parse().with_context(|| {
+                format!("parsing cache level of CPU {cpu} failed")
+            })?,
+            kind: read("type"),
+            size: read("size"),
+            shared: read_list(path.join("shared_cpu_list"))?,
+        });
+    }
+
+    caches.sort_by_key(|cache| (cache.level, cache.name()));
+
+    Ok(caches)
+}
+
+/// A CPU cache.
+struct Cache {
+    level: u8,
+    kind: String,
+    size: String,
+    shared: Vec<usize>,
+}
+
+impl Cache {
+    /// Returns the conventional name of this cache, e.g. `L1d`.
+    fn name(&self) -> String {
+        match self.kind.as_str() {
+            "Data" => format!("L{}d", self.level),
+            "Instruction" => format!("L{}i", self.level),
+            _ => format!("L{}", self.level),
+        }
+    }
+}
+
+/// Returns the CPUs isolated from general scheduling, i.e. the union of
+/// `isolcpus` and `nohz_full`.
+pub fn read_isolated() -> Result<Vec<usize>> {
+    let mut cpus = read_optional_list("isolated")?;
+    cpus.extend(read_optional_list("nohz_full")?);
+    cpus.sort_unstable();
+    cpus.dedup();
+
+    Ok(cpus)
+}
+
+/// Reads a cpulist file below `/sys/devices/system/cpu` that may not exist.
+fn read_optional_list(name: &str) -> Result<Vec<usize>> {
+    let path = Path::new(SYS_CPU).join(name);
+
+    if fs::exists(&path)? {
+        read_list(path)
+    } else {
+        Ok(vec![])
+    }
+}
+
+/// Returns the CPUs of each core type on hybrid CPUs, e.g. `cpu_core` and
+/// `cpu_atom`, empty otherwise.
+fn read_core_types() -> Result<Vec<(String, Vec<usize>)>> {
+    let mut types = vec![];
+
+    for entry in fs::read_dir("/sys/devices")? {
+        let path = entry?.path();
+
+        let Some(name) = path.file_name().and_then(|name| name.to_str())
+        else {
+            continue;
+        };
+
+        if name.starts_with("cpu_") && fs::exists(path.join("cpus"))? {
+            types.push((name.to_owned(), read_list(path.join("cpus"))?));
+        }
+    }
+
+    types.sort();
+
+    Ok(types)
+}
+
+// ----------------------------------------------------------------------------
+// tests
+// ----------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::{Cpu, Placement, Topology, resolve};
+
+    /// 2 sockets with 2 cores with 2 SMT siblings each, siblings are numbered
+    /// like on Intel, i.e. CPU N and N+4 share a core.
+    fn topology() -> Topology {
+        let cpus = (0..8)
+            .map(|id| Cpu {
+                id,
+                socket: (id % 4) / 2,
+                node: Some((id % 4) / 2),
+                core: id % 4,
+                thread: id / 4,
+                l3: Some((id % 4) / 2),
+            })
+            .collect();
+
+        Topology { cpus }
+    }
+
+    #[test]
+    fn order() {
+        let topology = topology();
+        let cpus: Vec<usize> = (0..8).chain([42]).collect();
+
+        assert_eq!(
+            topology.order(&cpus, Placement::Compact),
+            [0, 4, 1, 5, 2, 6, 3, 7, 42]
+        );
+
+        assert_eq!(
+            topology.order(&cpus, Placement::Scatter),
+            [0, 2, 1, 3, 4, 6, 5, 7, 42]
+        );
+
+        assert_eq!(
+            topology.order(&cpus, Placement::RoundRobin),
+            [0, 1, 2, 3, 4, 5, 6, 7, 42]
+        );
+    }
+
+    #[test]
+    fn resolve_without_topology() {
+        let resolved = |s: &str| resolve(&s.parse().unwrap()).unwrap();
+
+        // plain CPU numbers do not need the topology, so they also resolve to
+        // CPUs that are not online
+        assert_eq!(resolved("8,0-3,2"), [0, 1, 2, 3, 8]);
+        assert_eq!(resolved("1000"), [1000]);
+    }
+}
@@ -0,0 +1,162 @@
+use std::collections::HashMap;
+use std::thread;
+use std::time::Duration;
+
+use anyhow::{Result, anyhow};
+use clap::ArgMatches;
+use procfs::process::Process;
+
+use crate::adjust::Adjustment;
+use crate::cpuset;
+use crate::log;
+use crate::tree::ProcessTree;
+use crate::tree::modify::{self, Modifier};
+use crate::tree::track::Task;
+use crate::util::pid::ProcessID;
+use crate::util::piderator;
+
+/// Runs `tree enforce` subcommand.
+pub fn run(args: &ArgMatches) -> Result<()> {
+    let interval = args
+        .get_one::<Duration>("interval")
+        .copied()
+        .expect("interval has a default value");
+
+    let Some((name, args)) = args.subcommand() else {
+        unreachable!("{}", crate::cli::SUBCOMMAND_REQUIRED)
+    };
+
+    if name == "affinity" && args.contains_id("distribute") {
+        return Err(anyhow!("--distribute can not be enforced"));
+    }
+
+    // new tasks inherit the already adjusted value of their parent
+    let relative = match name {
+        "affinity" => !matches!(
+            args.get_one::<cpuset::Change>("cpuset"),
+            Some(cpuset::Change::Set(_))
+        ),
+        "nice" => matches!(
+            args.get_one::<Adjustment<i32>>("niceness"),
+            Some(Adjustment::Relative(_))
+        ),
+        "oom_score_adj" => matches!(
+            args.get_one::<Adjustment<i16>>("oom_score_adj"),
+            Some(Adjustment::Relative(_))
+        ),
+        _ => false,
+    };
+
+    if relative {
+        return Err(anyhow!(
+            "relative adjustments can not be enforced, forked children \
+             would get them on top of the inherited ones"
+        ));
+    }
+
+    let modifier = modify::modifier(name, args)?;
+
+    let args = modify::targets(args);
+    let verbose = args.get_flag("verbose");
+
+    let mut roots = piderator::args_or_stdin(args)
+        .filter_map(|pid| Task::of(ProcessID(pid)))
+        .collect::<Vec<_>>();
+
+    let myself = i32::try_from(std::process::id())?;
+
+    // tasks that have been handled, with their setting right afterwards
+    let mut handled: HashMap<Task, Option<String>> = HashMap::new();
+
+    loop {
+        roots.retain(|root| root.is_alive());
+
+        if roots.is_empty() {
+            break;
+        }
+
+        let mut current = HashMap::new();
+
+        for root in &roots {
+            let tree = ProcessTree::new(root.pid, modifier.threads)?;
+
+            let pids = if modifier.skip_root {
+                tree.children.iter().flat_map(ProcessTree::pids).collect()
+            } else {
+                tree.pids()
+            };
+
+            for pid in pids {
+                if pid.0 == myself {
+                    continue;
+                }
+
+                // trees of several roots may overlap
+                let Some(task) =
+                    Task::of(pid).filter(|task| !current.contains_key(task))
+                else {
+                    continue;
+                };
+
+                let Ok(process) = pid.into_process() else {
+                    continue;
+                };
+
+                let state = match handled.remove(&task) {
+                    Some(state) => enforce(&modifier, process, state, verbose),
+                    None => apply(&modifier, process),
+                };
+
+                current.insert(task, state);
+            }
+        }
+
+        // forget tasks that have exited
+        handled = current;
+
+        thread::sleep(interval);
+    }
+
+    Ok(())
+}
+
+/// Modifies a task and returns its setting afterwards, or `None` if it
+/// failed or the setting can not be read.
+fn apply(modifier: &Modifier, process: Process) -> Option<String> {
+    let pid = process.pid;
+
+    if let Err(e) = modifier.apply(process) {
+        // not retried, to not repeat the error every interval
+        log::error(format!("{e}"));
+        return None;
+    }
+
+    let process = Process::new(pid).ok()?;
+    modifier.state(&process)?.ok()
+}
+
+/// Modifies a handled task again, if its setting has drifted from the one
+/// right after it was handled. Returns its setting afterwards.
+fn enforce(
+    modifier: &Modifier,
+    process: Process,
+    state: Option<String>,
+    verbose: bool,
+) -> Option<String> {
+    let before = state?;
+    let now = modifier.state(&process)?.ok()?;
+
+    if now == before {
+        return Some(before);
+    }
+
+    if verbose {
+        let comm = process
+            .stat()
+            .map_or_else(|_| String::from("?"), |stat| stat.comm);
+
+        eprintln!("process {} {comm} drifted: {before} -> {now}", process.pid);
+    }
+
+    apply(modifier, process)
+}
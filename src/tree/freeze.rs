@@ -0,0 +1,125 @@
+use std::collections::HashSet;
+use std::path::PathBuf;
+use std::thread;
+use std::time::Duration;
+
+use anyhow::Result;
+use clap::ArgMatches;
+use procfs::process::Process;
+
+use crate::cgroup;
+use crate::signal::Signal;
+use crate::tree::track::{self, Tracker};
+use crate::util::piderator;
+
+/// Time to wait for the kernel to freeze or thaw a cgroup.
+const FREEZE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Time for stop signals to be delivered before taking the next snapshot.
+const SETTLE_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Runs `tree suspend` subcommand.
+pub fn run_suspend(args: &ArgMatches) -> Result<()> {
+    let verbose = args.get_flag("verbose");
+
+    for pid in piderator::args_or_stdin(args) {
+        let mut tracker = Tracker::new([pid])?;
+
+        if let Some(dir) = owned_cgroup(pid, &mut tracker)? {
+            if verbose {
+                eprintln!("freezing cgroup {}", dir.display());
+            }
+
+            cgroup::freeze(&dir, true, FREEZE_TIMEOUT)?;
+            continue;
+        }
+
+        // parents before their children, and again until a snapshot shows
+        // no new children, which the parents forked before they stopped
+        let mut stopped = HashSet::new();
+
+        loop {
+            let new = tracker
+                .alive()?
+                .into_iter()
+                .filter(|task| !stopped.contains(task))
+                .collect::<Vec<_>>();
+
+            if new.is_empty() {
+                break;
+            }
+
+            for task in new {
+                track::send(task, Signal(libc::SIGSTOP), verbose);
+                stopped.insert(task);
+            }
+
+            thread::sleep(SETTLE_INTERVAL);
+        }
+    }
+
+    Ok(())
+}
+
+/// Runs `tree resume` subcommand.
+pub fn run_resume(args: &ArgMatches) -> Result<()> {
+    let verbose = args.get_flag("verbose");
+
+    for pid in piderator::args_or_stdin(args) {
+        let mut tracker = Tracker::new([pid])?;
+
+        if let Some(dir) = owned_cgroup(pid, &mut tracker)? {
+            if cgroup::is_frozen(&dir)? {
+                if verbose {
+                    eprintln!("thawing cgroup {}", dir.display());
+                }
+
+                cgroup::freeze(&dir, false, FREEZE_TIMEOUT)?;
+                continue;
+            }
+        }
+
+        for task in tracker.alive()? {
+            track::send(task, Signal(libc::SIGCONT), verbose);
+        }
+    }
+
+    Ok(())
+}
+
+/// Returns the cgroup of the root process if it can be frozen, contains
+/// only processes of the tree, including its descendant cgroups, and all
+/// processes of the tree are in it or below it.
+fn owned_cgroup(pid: i32, tracker: &mut Tracker) -> Result<Option<PathBuf>> {
+    let Ok(process) = Process::new(pid) else {
+        return Ok(None);
+    };
+
+    let Some(dir) = cgroup::dir(&process)? else {
+        return Ok(None);
+    };
+
+    if !cgroup::can_freeze(&dir) {
+        return Ok(None);
+    }
+
+    let tasks = tracker.alive()?;
+
+    // descendants moved to another cgroup would escape freezing
+    for task in &tasks {
+        let inside = Process::new(task.pid)
+            .ok()
+            .and_then(|process| cgroup::dir(&process).ok().flatten())
+            .is_some_and(|task_dir| task_dir.starts_with(&dir));
+
+        // processes may have exited in the meantime
+        if !inside && task.is_alive() {
+            return Ok(None);
+        }
+    }
+
+    let tree = tasks.iter().map(|task| task.pid).collect::<HashSet<_>>();
+    let owned = cgroup::procs(&dir)?.iter().all(|pid| tree.contains(pid));
+
+    Ok(owned.then_some(dir))
+}
@@ -1,7 +1,14 @@
-mod modify;
+mod enforce;
+mod freeze;
+pub mod modify;
 mod show;
+mod signal;
+mod terminate;
+mod track;
+mod wait;
 
 use std::collections::HashMap;
+use std::process::ExitCode;
 
 use anyhow::{Context, Result};
 use clap::ArgMatches;
@@ -16,12 +23,20 @@ use crate::util::pid::ProcessID;
 // ----------------------------------------------------------------------------
 
 /// Runs `tree` subcommand.
-pub fn run(args: &ArgMatches) -> Result<()> {
+pub fn run(args: &ArgMatches) -> Result<ExitCode> {
     match args.subcommand() {
-        Some(("modify", args)) => modify::run(args),
-        Some(("show", args)) => show::run(args),
+        Some(("enforce", args)) => enforce::run(args)?,
+        Some(("modify", args)) => modify::run(args)?,
+        Some(("resume", args)) => freeze::run_resume(args)?,
+        Some(("show", args)) => show::run(args)?,
+        Some(("signal", args)) => signal::run(args)?,
+        Some(("suspend", args)) => freeze::run_suspend(args)?,
+        Some(("terminate", args)) => terminate::run(args)?,
+        Some(("wait", args)) => return wait::run(args),
         _ => unreachable!("{}", crate::cli::SUBCOMMAND_REQUIRED),
     }
+
+    Ok(ExitCode::SUCCESS)
 }
 
 // ----------------------------------------------------------------------------
@@ -38,7 +53,7 @@ struct ProcessTree {
     root: ProcessID,
 
     /// The children of this tree.
-    children: Vec<ProcessTree>,
+    children: Vec<Self>,
 }
 
 impl ProcessTree {
@@ -53,12 +68,14 @@ impl ProcessTree {
         for process in procfs::process::all_processes()
             .context("reading all processes failed")?
         {
-            let process = process?;
+            // processes may exit while reading them
+            let Ok(stat) = process.and_then(|process| process.stat()) else {
+                continue;
+            };
 
-            let children =
-                procs.entry(ProcessID(process.stat()?.ppid)).or_default();
+            let children = procs.entry(ProcessID(stat.ppid)).or_default();
 
-            children.push(ProcessID(process.pid));
+            children.push(ProcessID(stat.pid));
         }
 
         tree.convert(&mut procs);
@@ -85,6 +102,32 @@ impl ProcessTree {
         }
     }
 
+    /// Returns the IDs of all tasks in this tree, parents before their
+    /// children.
+    fn pids(&self) -> Vec<ProcessID> {
+        let mut pids = vec![self.root];
+
+        for child in &self.children {
+            pids.extend(child.pids());
+        }
+
+        pids
+    }
+
+    /// Returns the IDs of all tasks in this tree, children before their
+    /// parents.
+    fn pids_bottom_up(&self) -> Vec<ProcessID> {
+        let mut pids = vec![];
+
+        for child in &self.children {
+            pids.extend(child.pids_bottom_up());
+        }
+
+        pids.push(self.root);
+
+        pids
+    }
+
     /// Recursively adds threads to the children of their respective parent
     /// processes in the tree.
     fn add_threads(&mut self) -> Result<()> {
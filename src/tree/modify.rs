@@ -2,110 +2,557 @@ use anyhow::{Result, anyhow};
 use clap::ArgMatches;
 use procfs::process::Process;
 
+use crate::adjust::Adjustment;
 use crate::affinity;
+use crate::cpuset;
+use crate::ioprio;
+use crate::log;
 use crate::nice;
+use crate::numa;
+use crate::rlimit;
+use crate::sched;
+use crate::topology::{self, Placement, Topology};
 use crate::tree::{ProcessTree, Threads};
 use crate::util::piderator;
 
 /// Runs `tree modify` subcommand.
 pub fn run(args: &ArgMatches) -> Result<()> {
     match args.subcommand() {
-        Some(("affinity", args)) => run_affinity(args),
-        Some(("nice", args)) => run_nice(args),
-        Some(("oom_score_adj", args)) => run_oom_score_adj(args),
+        Some(("affinity", args)) if args.contains_id("distribute") => {
+            run_distribute(args)
+        }
+        Some((name, args)) => {
+            modify_tree(targets(args), &modifier(name, args)?)
+        }
+        None => unreachable!("{}", crate::cli::SUBCOMMAND_REQUIRED),
+    }
+}
+
+/// Runs `tree modify affinity --distribute` subcommand.
+fn run_distribute(args: &ArgMatches) -> Result<()> {
+    let change = args
+        .get_one::<cpuset::Change>("cpuset")
+        .expect("cpuset is a required argument");
+
+    let cpuset::Change::Set(_) = change else {
+        return Err(anyhow!("--distribute requires an absolute cpuset"));
+    };
+
+    let placement =
+        match args.get_one::<String>("distribute").map(String::as_str) {
+            Some("compact") => Placement::Compact,
+            Some("scatter") => Placement::Scatter,
+            _ => Placement::RoundRobin,
+        };
+
+    let group_size = args
+        .get_one::<usize>("group_size")
+        .copied()
+        .expect("group size has a default value");
+
+    let topology = Topology::read()?;
+    let online = topology.online();
+
+    let cpus = topology.resolve(change.spec())?;
+    if cpus.is_empty() {
+        return Err(anyhow!("cpuset resolves to no CPUs"));
+    }
+
+    let cpus = topology.order(&cpus, placement);
+
+    distribute(args, &cpus, &online, group_size)
+}
+
+/// Hands out groups of `cpus` to the tasks of the process trees in turn,
+/// starting over when running out of CPUs, and prints the assignment.
+fn distribute(
+    args: &ArgMatches,
+    cpus: &[usize],
+    online: &[usize],
+    group_size: usize,
+) -> Result<()> {
+    let mut groups = cpus.chunks(group_size).cycle();
+
+    for pid in piderator::args_or_stdin(args) {
+        let tree = ProcessTree::new(pid, Threads(true))?;
+
+        for task in tree.pids() {
+            let group = groups.next().expect("cpuset is not empty");
+
+            let assignment = task.into_process().and_then(|process| {
+                let cmd = process.stat()?.comm;
+                let permitted = affinity::permitted(&process, online)?;
+                let cpus = affinity::set_permitted(
+                    &process,
+                    group.to_vec(),
+                    &permitted,
+                )?;
+
+                Ok(format!("{} {cmd} {}", task.0, cpuset::format_list(&cpus)))
+            });
+
+            match assignment {
+                Ok(assignment) => println!("{assignment}"),
+                Err(e) => log::error(format!("{e}")),
+            }
+        }
+    }
+
+    Ok(())
+}
+
+// ----------------------------------------------------------------------------
+// modifiers
+// ----------------------------------------------------------------------------
+
+/// Modifies a task.
+type Apply<'a> = Box<dyn Fn(Process) -> Result<()> + 'a>;
+
+/// Returns the modified setting of a task.
+type State<'a> = Box<dyn Fn(&Process) -> Result<String> + 'a>;
+
+/// How a `tree modify` subcommand modifies a single task.
+pub struct Modifier<'a> {
+    /// Whether threads are modified on their own or with their process.
+    pub(super) threads: Threads,
+
+    /// Whether the root of each tree is left alone.
+    pub(super) skip_root: bool,
+
+    /// Whether each modified task is printed, with its setting before and
+    /// after if there is a state to read it.
+    verbose: bool,
+
+    apply: Apply<'a>,
+    state: Option<State<'a>>,
+}
+
+impl<'a> Modifier<'a> {
+    fn new<F>(threads: Threads, verbose: bool, apply: F) -> Self
+    where
+        F: Fn(Process) -> Result<()> + 'a,
+    {
+        Self {
+            threads,
+            skip_root: false,
+            verbose,
+            apply: Box::new(apply),
+            state: None,
+        }
+    }
+
+    /// Sets how to read the modified setting of a task.
+    fn with_state<F>(mut self, state: F) -> Self
+    where
+        F: Fn(&Process) -> Result<String> + 'a,
+    {
+        self.state = Some(Box::new(state));
+        self
+    }
+
+    /// Modifies a task.
+    pub fn apply(&self, process: Process) -> Result<()> {
+        if !self.verbose {
+            return (self.apply)(process);
+        }
+
+        let pid = process.pid;
+        let cmd = process.stat()?.comm;
+        let before = self.state(&process).and_then(Result::ok);
+
+        (self.apply)(process)?;
+
+        let after = Process::new(pid)
+            .ok()
+            .and_then(|process| self.state(&process))
+            .and_then(Result::ok);
+
+        match (before, after) {
+            (Some(before), Some(after)) => {
+                eprintln!(
+                    "modifying process {pid} {cmd}: {before} -> {after}"
+                );
+            }
+
+            _ => eprintln!("modifying process {pid} {cmd}"),
+        }
+
+        Ok(())
+    }
+
+    /// Modifies a process, or each of its threads if they are modified on
+    /// their own.
+    pub fn apply_threads(&self, process: &Process) -> Result<()> {
+        if !self.threads.0 {
+            return self.apply(Process::new(process.pid)?);
+        }
+
+        let tids = process
+            .tasks()?
+            .flatten()
+            .map(|task| task.tid)
+            .collect::<Vec<_>>();
+
+        for tid in tids {
+            // threads may exit in the meantime
+            if let Ok(thread) = Process::new(tid) {
+                self.apply(thread)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns the modified setting of a task, to tell if it has been
+    /// changed by someone else since, or `None` if it can not be read.
+    pub fn state(&self, process: &Process) -> Option<Result<String>> {
+        self.state.as_ref().map(|state| state(process))
+    }
+}
+
+/// Returns the modifier of a `tree modify` subcommand.
+pub fn modifier<'a>(name: &str, args: &'a ArgMatches) -> Result<Modifier<'a>> {
+    match name {
+        "affinity" => affinity(args),
+        "ioprio" => Ok(ioprio(args)),
+        "limit" => Ok(limit(args)),
+        "nice" => Ok(nice(args)),
+        "numa" => Ok(numa(args)),
+        "oom_score_adj" => oom_score_adj(args),
+        "sched" => Ok(sched(args)),
+        "uclamp" => uclamp(args),
         _ => unreachable!("{}", crate::cli::SUBCOMMAND_REQUIRED),
     }
 }
 
-/// Runs `tree modify affinity` subcommand.
-fn run_affinity(args: &ArgMatches) -> Result<()> {
+/// Returns the modifier of `tree modify affinity` subcommand.
+fn affinity(args: &ArgMatches) -> Result<Modifier<'_>> {
     let verbose = args.get_flag("verbose");
 
-    let cpuset: Vec<usize> = match args
-        .get_one::<String>("cpuset")
-        .map(String::as_str)
-        .expect("cpuset is a required argument")
-    {
-        "free" => (0..libc::CPU_SETSIZE as usize).collect(),
-        cpuset => vec![cpuset.parse().unwrap()],
+    let change = args
+        .get_one::<cpuset::Change>("cpuset")
+        .expect("cpuset is a required argument");
+
+    let online = topology::online()?;
+    let requested = affinity::requested(change)?;
+
+    Ok(set_affinity(change, requested, online, verbose))
+}
+
+/// Returns the modifier of `tree modify ioprio` subcommand.
+fn ioprio(args: &ArgMatches) -> Modifier<'_> {
+    let Some((class, args)) = args.subcommand() else {
+        unreachable!("{}", crate::cli::SUBCOMMAND_REQUIRED)
     };
 
-    let f = |process: Process| {
-        if verbose {
-            let pid = &process.pid;
+    let verbose = args.get_flag("verbose");
+
+    let class = class
+        .parse::<ioprio::Class>()
+        .expect("subcommands are classes");
+
+    let level = if class.has_level() {
+        args.get_one::<u8>("level")
+            .copied()
+            .expect("level is a required argument of classes with level")
+    } else {
+        0
+    };
+
+    let ioprio = ioprio::IoPriority { class, level };
+
+    set_ioprio(ioprio, verbose)
+}
+
+/// Returns the modifier of `tree modify limit` subcommand.
+fn limit(args: &ArgMatches) -> Modifier<'_> {
+    let verbose = args.get_flag("verbose");
+
+    let resource = args
+        .get_one::<rlimit::Resource>("resource")
+        .copied()
+        .expect("resource is a required argument");
+
+    let setting = args
+        .get_one::<rlimit::Setting>("limit")
+        .copied()
+        .expect("limit is a required argument");
+
+    let f = move |process: Process| {
+        let pid = process.pid;
+
+        let after = setting.apply(rlimit::get(pid, resource)?);
+
+        if after.soft > after.hard {
             let cmd = &process.stat()?.comm;
-            eprintln!("modifying process {pid} {cmd}");
+
+            return Err(anyhow!(
+                "process {pid} {cmd}: soft limit {} exceeds hard limit {}",
+                after.soft,
+                after.hard,
+            ));
         }
 
-        affinity::set(process.pid, &cpuset)
+        rlimit::set(pid, resource, after)?;
+
+        Ok(())
+    };
+
+    let state = move |process: &Process| {
+        Ok(format!(
+            "{resource} {}",
+            rlimit::get(process.pid, resource)?
+        ))
     };
 
-    modify_tree(args, f)
+    // limits are shared by all threads of a process
+    Modifier::new(Threads(false), verbose, f).with_state(state)
 }
 
-/// Runs `tree modify nice` subcommand.
-fn run_nice(args: &ArgMatches) -> Result<()> {
+/// Returns the modifier of `tree modify nice` subcommand.
+fn nice(args: &ArgMatches) -> Modifier<'_> {
     let verbose = args.get_flag("verbose");
 
-    let niceness = args
-        .get_one::<i32>("niceness")
+    let adjustment = args
+        .get_one::<Adjustment<i32>>("niceness")
         .copied()
         .expect("niceness is a required argument");
 
-    let f = |process: Process| {
-        if verbose {
-            let pid = &process.pid;
-            let cmd = &process.stat()?.comm;
-            eprintln!("modifying process {pid} {cmd}");
+    set_nice(adjustment, verbose)
+}
+
+/// Returns the modifier of `tree modify numa` subcommand.
+fn numa(args: &ArgMatches) -> Modifier<'_> {
+    let verbose = args.get_flag("verbose");
+
+    let nodes = args
+        .get_one::<Vec<usize>>("nodes")
+        .expect("nodes is a required argument");
+
+    let f = move |process: Process| {
+        let pid = process.pid;
+        let cmd = &process.stat()?.comm;
+
+        let failed = numa::migrate(&process, nodes)?;
+
+        if failed > 0 {
+            log::warn(format!(
+                "process {pid} {cmd}: {failed} pages could not be moved"
+            ));
         }
 
-        // need to convert into u32 as required by libc::getpriority
-        process.pid.try_into().map_or_else(
-            |_| Err(anyhow!("invalid process id: {}", process.pid)),
-            |pid| nice::set(pid, niceness),
-        )
+        Ok(())
     };
 
-    modify_tree(args, f)
+    // threads share the memory of their process, which is migrated once
+    // and not moved back by anyone else, so there is no state to compare
+    Modifier::new(Threads(false), verbose, f)
 }
 
-/// Runs `tree modify oom_score_adj` subcommand.
-fn run_oom_score_adj(args: &ArgMatches) -> Result<()> {
+/// Returns the modifier of `tree modify oom_score_adj` subcommand.
+fn oom_score_adj(args: &ArgMatches) -> Result<Modifier<'_>> {
     let verbose = args.get_flag("verbose");
 
     let adjustment = args
-        .get_one::<i16>("oom_score_adj")
+        .get_one::<Adjustment<i16>>("oom_score_adj")
         .copied()
         .expect("oom score adjustment is a required argument");
 
-    let f = |process: Process| {
-        if verbose {
-            let pid = &process.pid;
-            let cmd = &process.stat()?.comm;
-            eprintln!("modifying process {pid} {cmd}");
+    let min = args
+        .get_one::<i16>("oom_score_adj_min")
+        .copied()
+        .expect("min has a default value");
+
+    let max = args
+        .get_one::<i16>("oom_score_adj_max")
+        .copied()
+        .expect("max has a default value");
+
+    if min > max {
+        return Err(anyhow!("minimum {min} exceeds maximum {max}"));
+    }
+
+    let mut modifier = set_oom_score_adj(adjustment, min, max, verbose);
+    modifier.skip_root = args.get_flag("protect_root");
+
+    Ok(modifier)
+}
+
+/// Returns the modifier of `tree modify sched` subcommand.
+fn sched(args: &ArgMatches) -> Modifier<'_> {
+    let Some((policy, args)) = args.subcommand() else {
+        unreachable!("{}", crate::cli::SUBCOMMAND_REQUIRED)
+    };
+
+    let verbose = args.get_flag("verbose");
+
+    let policy = policy
+        .parse::<sched::Policy>()
+        .expect("subcommands are policies");
+
+    let priority = if policy.is_realtime() {
+        args.get_one::<i32>("priority")
+            .copied()
+            .expect("priority is a required argument of realtime policies")
+    } else {
+        0
+    };
+
+    let scheduling = sched::Scheduling { policy, priority };
+
+    set_sched(scheduling, verbose)
+}
+
+/// Returns the modifier of `tree modify uclamp` subcommand.
+fn uclamp(args: &ArgMatches) -> Result<Modifier<'_>> {
+    let verbose = args.get_flag("verbose");
+
+    let min = args.get_one::<u32>("uclamp_min").copied();
+    let max = args.get_one::<u32>("uclamp_max").copied();
+
+    if let (Some(min), Some(max)) = (min, max) {
+        if min > max {
+            return Err(anyhow!(
+                "minimum utilization {min} exceeds maximum utilization {max}"
+            ));
         }
+    }
+
+    let f = move |process: Process| sched::set_uclamp(process.pid, min, max);
+
+    let state = |process: &Process| {
+        Ok(match sched::get_attr(process.pid)?.uclamp {
+            Some((min, max)) => format!("{min}-{max}"),
+            None => String::from("unsupported"),
+        })
+    };
+
+    Ok(Modifier::new(Threads(true), verbose, f).with_state(state))
+}
+
+// ----------------------------------------------------------------------------
+// setters
+// ----------------------------------------------------------------------------
+
+/// Returns a modifier that changes the affinity of each thread, to the
+/// CPUs `requested` by `change` that are online.
+pub fn set_affinity(
+    change: &cpuset::Change,
+    requested: Option<Vec<usize>>,
+    online: Vec<usize>,
+    verbose: bool,
+) -> Modifier<'_> {
+    let f = move |process: Process| {
+        affinity::change(&process, change, requested.as_deref(), &online)
+            .map(|_| ())
+    };
+
+    let state = |process: &Process| {
+        Ok(cpuset::format_list(&affinity::get(process.pid)?))
+    };
+
+    Modifier::new(Threads(true), verbose, f).with_state(state)
+}
+
+/// Returns a modifier that sets the I/O priority of each thread.
+pub fn set_ioprio(
+    ioprio: ioprio::IoPriority,
+    verbose: bool,
+) -> Modifier<'static> {
+    let f = move |process: Process| ioprio::set(process.pid, ioprio);
+
+    let state = |process: &Process| Ok(ioprio::get(process.pid)?.to_string());
+
+    Modifier::new(Threads(true), verbose, f).with_state(state)
+}
 
-        process.set_oom_score_adj(adjustment)?;
+/// Returns a modifier that adjusts the niceness of each thread.
+pub fn set_nice(
+    adjustment: Adjustment<i32>,
+    verbose: bool,
+) -> Modifier<'static> {
+    let f = move |process: Process| {
+        let pid = nice_pid(&process)?;
+
+        let after = adjustment.apply(nice::get(pid)?, -20, 19);
+
+        nice::set(pid, after)
+    };
+
+    let state =
+        |process: &Process| Ok(nice::get(nice_pid(process)?)?.to_string());
+
+    Modifier::new(Threads(true), verbose, f).with_state(state)
+}
+
+/// Returns a modifier that adjusts the oom score adjustment of each process,
+/// clamped to `min..=max`.
+pub fn set_oom_score_adj(
+    adjustment: Adjustment<i16>,
+    min: i16,
+    max: i16,
+    verbose: bool,
+) -> Modifier<'static> {
+    let f = move |process: Process| {
+        let after = adjustment.apply(process.oom_score_adj()?, min, max);
+
+        process.set_oom_score_adj(after)?;
 
         Ok(())
     };
 
-    modify_tree(args, f)
+    let state = |process: &Process| Ok(process.oom_score_adj()?.to_string());
+
+    // the value is shared by all threads of a process, relative adjustments
+    // must not be applied once per thread
+    Modifier::new(Threads(false), verbose, f).with_state(state)
+}
+
+/// Returns a modifier that sets the scheduling policy of each thread.
+pub fn set_sched(
+    scheduling: sched::Scheduling,
+    verbose: bool,
+) -> Modifier<'static> {
+    let f = move |process: Process| sched::set(process.pid, scheduling);
+
+    let state = |process: &Process| Ok(sched::get(process.pid)?.to_string());
+
+    Modifier::new(Threads(true), verbose, f).with_state(state)
 }
 
 // ----------------------------------------------------------------------------
 // helper
 // ----------------------------------------------------------------------------
 
-/// Modify process tree from arguments or STDIN with changes from `f`.
-fn modify_tree<F>(args: &ArgMatches, f: F) -> Result<()>
-where
-    F: Fn(Process) -> Result<()>,
-{
+/// Returns the arguments with the PIDs of a `tree modify` subcommand, which
+/// are those of the class or policy subcommand for `ioprio` and `sched`.
+pub fn targets(args: &ArgMatches) -> &ArgMatches {
+    args.subcommand().map_or(args, |(_, args)| args)
+}
+
+/// Modify process tree from arguments or STDIN with `modifier`.
+fn modify_tree(args: &ArgMatches, modifier: &Modifier) -> Result<()> {
+    let f = |process: Process| modifier.apply(process);
+
     for pid in piderator::args_or_stdin(args) {
-        let tree = ProcessTree::new(pid, Threads(true))?;
-        tree.modify(&f);
+        let tree = ProcessTree::new(pid, modifier.threads)?;
+
+        if modifier.skip_root {
+            for child in &tree.children {
+                child.modify(&f);
+            }
+        } else {
+            tree.modify(&f);
+        }
     }
 
     Ok(())
 }
+
+/// Returns the PID as required by `libc::getpriority`.
+fn nice_pid(process: &Process) -> Result<u32> {
+    process
+        .pid
+        .try_into()
+        .map_err(|_| anyhow!("invalid process id: {}", process.pid))
+}
@@ -1,3 +1,5 @@
+use std::cell::Cell;
+use std::collections::{BTreeMap, BTreeSet};
 use std::process::Command;
 
 use anyhow::{Result, anyhow};
@@ -5,8 +7,17 @@ use clap::ArgMatches;
 use procfs::process::Process;
 
 use crate::affinity;
+use crate::cgroup;
+use crate::cpuset;
+use crate::ioprio;
+use crate::log;
 use crate::nice;
+use crate::numa;
+use crate::rlimit;
+use crate::sched;
+use crate::topology::{self, Topology};
 use crate::tree::{ProcessTree, Threads};
+use crate::util;
 use crate::util::pid::ProcessID;
 use crate::util::piderator;
 
@@ -15,21 +26,82 @@ pub fn run(args: &ArgMatches) -> Result<()> {
     match args.subcommand() {
         Some(("affinity", args)) => run_affinity(args),
         Some(("backtrace", args)) => run_backtrace(args),
+        Some(("ioprio", args)) => run_ioprio(args),
+        Some(("limits", args)) => run_limits(args),
         Some(("nice", args)) => run_nice(args),
+        Some(("numa", args)) => run_numa(args),
         Some(("oom_score", args)) => run_oom_score(args),
         Some(("oom_score_adj", args)) => run_oom_score_adj(args),
+        Some(("placement", args)) => run_placement(args),
         Some(("plain", args)) => run_plain(args),
+        Some(("sched", args)) => run_sched(args),
+        Some(("sched_attr", args)) => run_sched_attr(args),
         _ => unreachable!("{}", crate::cli::SUBCOMMAND_REQUIRED),
     }
 }
 
 /// Runs `tree show affinity` subcommand.
 fn run_affinity(args: &ArgMatches) -> Result<()> {
+    let format = args
+        .get_one::<String>("format")
+        .map(String::as_str)
+        .expect("format has a default value");
+
+    let topology = if format == "numa" {
+        Some(Topology::read()?)
+    } else {
+        None
+    };
+
+    let isolated = if args.get_flag("check_isolated") {
+        let isolated = topology::read_isolated()?;
+
+        if isolated.is_empty() {
+            log::warn("there are no isolated or nohz_full CPUs");
+        }
+
+        Some(isolated)
+    } else {
+        None
+    };
+
+    let flagged = Cell::new(0_usize);
+
     let payload = |process: Process| {
-        affinity::get(process.pid).map(|affinity| format!("{affinity:?}"))
+        let cpus = affinity::get(process.pid)?;
+
+        let mut payload = match &topology {
+            Some(topology) => format_per_node(topology, &cpus),
+            None if format == "mask" => cpuset::format_mask(&cpus),
+            None => cpuset::format_list(&cpus),
+        };
+
+        if let Some(isolated) = &isolated {
+            let (on, off): (Vec<usize>, Vec<usize>) =
+                cpus.iter().partition(|cpu| isolated.contains(cpu));
+
+            if !on.is_empty() {
+                flagged.set(flagged.get() + 1);
+
+                payload.push_str(" ! isolated ");
+                payload.push_str(&cpuset::format_list(&on));
+
+                if !off.is_empty() {
+                    payload.push_str(" spills to housekeeping ");
+                    payload.push_str(&cpuset::format_list(&off));
+                }
+            }
+        }
+
+        Ok(payload)
     };
 
-    print_tree(args, payload)
+    print_tree(args, payload)?;
+
+    match flagged.get() {
+        0 => Ok(()),
+        n => Err(anyhow!("{n} tasks may run on isolated CPUs")),
+    }
 }
 
 /// Runs `tree show backtrace` subcommand.
@@ -84,6 +156,44 @@ fn run_backtrace(args: &ArgMatches) -> Result<()> {
     print_tree(args, payload)
 }
 
+/// Runs `tree show ioprio` subcommand.
+fn run_ioprio(args: &ArgMatches) -> Result<()> {
+    let payload = |process: Process| {
+        ioprio::get(process.pid).map(|ioprio| format!("{ioprio}"))
+    };
+
+    print_tree(args, payload)
+}
+
+/// Runs `tree show limits` subcommand.
+fn run_limits(args: &ArgMatches) -> Result<()> {
+    let (resource, args) = match args.subcommand() {
+        Some((name, args)) => {
+            let resource = name
+                .parse::<rlimit::Resource>()
+                .expect("subcommands are resources");
+
+            (Some(resource), args)
+        }
+
+        None => (None, args),
+    };
+
+    let payload = |process: Process| {
+        let limits = rlimit::read(&process)?;
+
+        let limits = limits
+            .into_iter()
+            .filter(|(r, _)| resource.is_none_or(|resource| resource == *r))
+            .map(|(resource, limit)| format!("{resource} {limit}"))
+            .collect::<Vec<_>>();
+
+        Ok(limits.join("\n"))
+    };
+
+    print_tree(args, payload)
+}
+
 /// Runs `tree show nice` subcommand.
 fn run_nice(args: &ArgMatches) -> Result<()> {
     let payload = |process: Process| {
@@ -99,8 +209,29 @@ fn run_nice(args: &ArgMatches) -> Result<()> {
     print_tree(args, payload)
 }
 
+/// Runs `tree show numa` subcommand.
+fn run_numa(args: &ArgMatches) -> Result<()> {
+    let page_size = numa::page_size();
+
+    let payload = |process: Process| {
+        let memory = numa::memory(process.pid)?;
+
+        Ok(memory
+            .into_iter()
+            .map(|(node, bytes)| format!("N{node}={}", bytes / page_size))
+            .collect::<Vec<_>>()
+            .join(" "))
+    };
+
+    print_tree(args, payload)
+}
+
 /// Runs `tree show oom_score` subcommand.
 fn run_oom_score(args: &ArgMatches) -> Result<()> {
+    if args.get_flag("ranked") {
+        return run_oom_score_ranked(args);
+    }
+
     let payload = |process: Process| {
         process
             .oom_score()
@@ -111,6 +242,70 @@ fn run_oom_score(args: &ArgMatches) -> Result<()> {
     print_tree(args, payload)
 }
 
+/// Runs `tree show oom_score --ranked` subcommand.
+fn run_oom_score_ranked(args: &ArgMatches) -> Result<()> {
+    let arguments = args.get_flag("arguments");
+
+    let mut pids = BTreeSet::new();
+
+    for pid in piderator::args_or_stdin(args) {
+        let tree = ProcessTree::new(pid, Threads(false))?;
+        pids.extend(tree.pids());
+    }
+
+    let mut ranking = vec![];
+
+    for pid in pids {
+        // processes may have exited in the meantime
+        let Ok(process) = pid.into_process() else {
+            continue;
+        };
+
+        let rank = || -> Result<_> {
+            let stat = process.stat()?;
+
+            let command = if arguments {
+                process.cmdline().ok().map(|cmd| cmd.join(" "))
+            } else {
+                None
+            };
+            let command = command.unwrap_or(stat.comm);
+
+            Ok((
+                process.oom_score()?,
+                process.oom_score_adj()?,
+                pid.0,
+                stat.rss * numa::page_size(),
+                cgroup::memory_max(&process)?,
+                command,
+            ))
+        };
+
+        match rank() {
+            Ok(rank) => ranking.push(rank),
+            Err(e) => log::error(format!("{e}")),
+        }
+    }
+
+    // highest score first, the larger process breaks ties
+    ranking.sort_by(|a, b| b.0.cmp(&a.0).then(b.3.cmp(&a.3)));
+
+    println!(
+        "{:>5} {:>5} {:>7} {:>7} {:>7} COMMAND",
+        "SCORE", "ADJ", "PID", "RSS", "LIMIT"
+    );
+
+    for (score, adj, pid, rss, limit, command) in ranking {
+        let limit =
+            limit.map_or_else(|| String::from("max"), util::format_bytes);
+        let rss = util::format_bytes(rss);
+
+        println!("{score:>5} {adj:>5} {pid:>7} {rss:>7} {limit:>7} {command}");
+    }
+
+    Ok(())
+}
+
 /// Runs `tree show oom_score_adj` subcommand.
 fn run_oom_score_adj(args: &ArgMatches) -> Result<()> {
     let payload = |process: Process| {
@@ -123,6 +318,52 @@ fn run_oom_score_adj(args: &ArgMatches) -> Result<()> {
     print_tree(args, payload)
 }
 
+/// Runs `tree show placement` subcommand.
+fn run_placement(args: &ArgMatches) -> Result<()> {
+    let topology = Topology::read()?;
+
+    let payload = |process: Process| {
+        let Some(cpu) = process.stat()?.processor else {
+            return Ok(String::from("cpu unknown"));
+        };
+
+        let cpu = usize::try_from(cpu)?;
+
+        let Some(node) = topology.node(cpu) else {
+            return Ok(format!("cpu {cpu}"));
+        };
+
+        // kernel threads have no memory, and reading the memory of others
+        // may not be permitted, in which case there is nothing to compare
+        match numa::dominant_node(process.pid).ok().flatten() {
+            Some(memory) if memory != node => Ok(format!(
+                "cpu {cpu} node {node} ! memory mostly on node {memory}"
+            )),
+            _ => Ok(format!("cpu {cpu} node {node}")),
+        }
+    };
+
+    print_tree(args, payload)
+}
+
+/// Runs `tree show sched` subcommand.
+fn run_sched(args: &ArgMatches) -> Result<()> {
+    let payload = |process: Process| {
+        sched::get(process.pid).map(|scheduling| format!("{scheduling}"))
+    };
+
+    print_tree(args, payload)
+}
+
+/// Runs `tree show sched_attr` subcommand.
+fn run_sched_attr(args: &ArgMatches) -> Result<()> {
+    let payload = |process: Process| {
+        sched::get_attr(process.pid).map(|attributes| format!("{attributes}"))
+    };
+
+    print_tree(args, payload)
+}
+
 /// Runs `tree show plain` subcommand.
 fn run_plain(args: &ArgMatches) -> Result<()> {
     let payload = |_: Process| Ok(String::new());
@@ -134,6 +375,31 @@ fn run_plain(args: &ArgMatches) -> Result<()> {
 // helper
 // ----------------------------------------------------------------------------
 
+/// Formats CPUs per NUMA node with the identifiers of cpuset selectors: a
+/// node with all of its CPUs as `node:0`, otherwise with its CPUs like
+/// `node:1=8-11`.
+fn format_per_node(topology: &Topology, cpus: &[usize]) -> String {
+    let mut nodes: BTreeMap<Option<usize>, Vec<usize>> = BTreeMap::new();
+
+    for &cpu in cpus {
+        nodes.entry(topology.node(cpu)).or_default().push(cpu);
+    }
+
+    nodes
+        .into_iter()
+        .map(|(node, cpus)| match node {
+            Some(node) if topology.node_cpus(node) == cpus => {
+                format!("node:{node}")
+            }
+            Some(node) => {
+                format!("node:{node}={}", cpuset::format_list(&cpus))
+            }
+            None => format!("node:?={}", cpuset::format_list(&cpus)),
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
 /// Print process tree from arguments or STDIN with content from payload
 /// function.
 fn print_tree<F>(args: &ArgMatches, payload: F) -> Result<()>
@@ -0,0 +1,75 @@
+use anyhow::{Result, anyhow};
+use clap::ArgMatches;
+
+use crate::log;
+use crate::signal::Signal;
+use crate::tree::track::Task;
+use crate::tree::{ProcessTree, Threads};
+use crate::util::piderator;
+
+/// Runs `tree signal` subcommand.
+pub fn run(args: &ArgMatches) -> Result<()> {
+    let dry_run = args.get_flag("dry_run");
+    let verbose = args.get_flag("verbose");
+
+    let signal = args
+        .get_one::<Signal>("signal")
+        .copied()
+        .expect("signal is a required argument");
+
+    let order = args
+        .get_one::<String>("order")
+        .expect("order has a default value");
+
+    // never signal ourselves, we might be part of the tree
+    let myself = i32::try_from(std::process::id())?;
+
+    let mut failed = 0;
+
+    for pid in piderator::args_or_stdin(args) {
+        let tree = ProcessTree::new(pid, Threads(false))?;
+
+        let pids = match order.as_str() {
+            "top-down" => tree.pids(),
+            _ => tree.pids_bottom_up(),
+        };
+
+        // identify the processes right away, so that processes reusing the
+        // PID of an exited one in the meantime are left alone
+        let tasks = pids
+            .into_iter()
+            .filter(|pid| pid.0 != myself)
+            .filter_map(Task::of)
+            .collect::<Vec<_>>();
+
+        for task in tasks {
+            // processes may have exited in the meantime
+            if !task.is_alive() {
+                continue;
+            }
+
+            let pid = task.pid;
+            let cmd = task.comm();
+
+            if dry_run {
+                println!("would send {signal} to {pid} {cmd}");
+                continue;
+            }
+
+            if verbose {
+                eprintln!("sending {signal} to {pid} {cmd}");
+            }
+
+            if let Err(e) = task.signal(signal) {
+                log::error(format!("{e}"));
+                failed += 1;
+            }
+        }
+    }
+
+    if failed == 0 {
+        Ok(())
+    } else {
+        Err(anyhow!("failed to signal {failed} processes"))
+    }
+}
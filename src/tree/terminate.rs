@@ -0,0 +1,96 @@
+use std::collections::HashSet;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use anyhow::{Result, anyhow};
+use clap::ArgMatches;
+
+use crate::log;
+use crate::signal::Signal;
+use crate::tree::track::{self, Tracker};
+use crate::util::piderator;
+
+/// Time between snapshots of the trees.
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Time to wait for killed processes to disappear.
+const KILL_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Runs `tree terminate` subcommand.
+pub fn run(args: &ArgMatches) -> Result<()> {
+    let verbose = args.get_flag("verbose");
+
+    let grace = args
+        .get_one::<Duration>("grace")
+        .copied()
+        .expect("grace has a default value");
+
+    let mut tracker = Tracker::new(piderator::args_or_stdin(args))?;
+
+    // children that are forked during the grace period get terminated too
+    let mut terminated = HashSet::new();
+    let deadline = Instant::now() + grace;
+
+    let survivors = loop {
+        let alive = tracker.alive()?;
+
+        if alive.is_empty() {
+            return Ok(());
+        }
+
+        if Instant::now() >= deadline {
+            break alive;
+        }
+
+        // children before their parents, stopped ones are continued to act
+        // on SIGTERM within the grace period, like kill(1) and systemd do
+        for &task in alive.iter().rev() {
+            if terminated.insert(task) {
+                track::send(task, Signal(libc::SIGTERM), verbose);
+                track::send(task, Signal(libc::SIGCONT), verbose);
+            }
+        }
+
+        thread::sleep(POLL_INTERVAL);
+    };
+
+    log::warn(format!(
+        "{} processes still alive after {grace:?}, killing them",
+        survivors.len()
+    ));
+
+    // parents before their children, so they can not fork any more
+    let mut killed = HashSet::new();
+    let deadline = Instant::now() + KILL_TIMEOUT;
+
+    let survivors = loop {
+        let alive = tracker.alive()?;
+
+        if alive.is_empty() {
+            return Ok(());
+        }
+
+        if Instant::now() >= deadline {
+            break alive;
+        }
+
+        for &task in &alive {
+            if killed.insert(task) {
+                track::send(task, Signal(libc::SIGKILL), verbose);
+            }
+        }
+
+        thread::sleep(POLL_INTERVAL);
+    };
+
+    let survivors = survivors
+        .into_iter()
+        .map(|task| format!("{} {}", task.pid, task.comm()))
+        .collect::<Vec<_>>();
+
+    Err(anyhow!(
+        "{} processes survived: {}",
+        survivors.len(),
+        survivors.join(", ")
+    ))
+}
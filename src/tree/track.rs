@@ -0,0 +1,123 @@
+use std::collections::HashSet;
+
+use anyhow::Result;
+use procfs::process::{ProcState, Process};
+
+use crate::log;
+use crate::signal::{self, Signal};
+use crate::tree::{ProcessTree, Threads};
+use crate::util::pid::ProcessID;
+
+/// A process identified by its PID and start time, which tells it apart
+/// from later processes that reuse the PID.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct Task {
+    pub pid: i32,
+    starttime: u64,
+}
+
+impl Task {
+    /// Returns the task currently running with the given PID, if there is
+    /// one that has not yet exited.
+    pub fn of(pid: ProcessID) -> Option<Self> {
+        let stat = Process::new(pid.0).ok()?.stat().ok()?;
+
+        match stat.state() {
+            Ok(ProcState::Zombie | ProcState::Dead) | Err(_) => None,
+            Ok(_) => Some(Self {
+                pid: pid.0,
+                starttime: stat.starttime,
+            }),
+        }
+    }
+
+    /// Returns true if this task has not yet exited.
+    pub fn is_alive(self) -> bool {
+        Self::of(ProcessID(self.pid)) == Some(self)
+    }
+
+    /// Returns the command name of this task.
+    pub fn comm(self) -> String {
+        Process::new(self.pid)
+            .and_then(|process| process.stat())
+            .map_or_else(|_| String::from("?"), |stat| stat.comm)
+    }
+
+    /// Sends a signal to this task, unless it has already exited.
+    pub fn signal(self, signal: Signal) -> Result<()> {
+        if self.is_alive() {
+            signal::send(self.pid, signal)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// Tracks the processes of trees across snapshots. Descendants stay tracked
+/// when their parents exit and they are reparented out of the tree.
+pub struct Tracker {
+    /// The tasks alive at the last snapshot, parents before their children.
+    tasks: Vec<Task>,
+
+    /// The PID of this process, which must never be part of a tree.
+    myself: i32,
+}
+
+impl Tracker {
+    /// Returns a new tracker of the trees with the given roots.
+    pub fn new(pids: impl IntoIterator<Item = i32>) -> Result<Self> {
+        let tasks = pids
+            .into_iter()
+            .filter_map(|pid| Task::of(ProcessID(pid)))
+            .collect();
+
+        Ok(Self {
+            tasks,
+            myself: i32::try_from(std::process::id())?,
+        })
+    }
+
+    /// Takes a new snapshot and returns all tasks that are alive, parents
+    /// before their children, including tasks that are new since the last
+    /// snapshot.
+    pub fn alive(&mut self) -> Result<Vec<Task>> {
+        let mut alive = vec![];
+        let mut covered = HashSet::new();
+
+        for task in self.tasks.clone() {
+            // a tree of an alive ancestor already contains this one
+            if covered.contains(&task.pid) || !task.is_alive() {
+                continue;
+            }
+
+            let tree = ProcessTree::new(task.pid, Threads(false))?;
+
+            for pid in tree.pids() {
+                if pid.0 == self.myself || !covered.insert(pid.0) {
+                    continue;
+                }
+
+                if let Some(task) = Task::of(pid) {
+                    alive.push(task);
+                }
+            }
+        }
+
+        // exited tasks never come back, their descendants are tracked on
+        // their own
+        self.tasks.clone_from(&alive);
+
+        Ok(alive)
+    }
+}
+
+/// Sends a signal to a task, logging failures.
+pub fn send(task: Task, signal: Signal, verbose: bool) {
+    if verbose {
+        eprintln!("sending {signal} to {} {}", task.pid, task.comm());
+    }
+
+    if let Err(e) = task.signal(signal) {
+        log::error(format!("{e}"));
+    }
+}
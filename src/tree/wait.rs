@@ -0,0 +1,72 @@
+use std::collections::HashMap;
+use std::process::ExitCode;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use anyhow::Result;
+use clap::ArgMatches;
+
+use crate::log;
+use crate::tree::track::{Task, Tracker};
+use crate::util::piderator;
+
+/// Time between snapshots of the trees.
+const POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+/// Exit code when timing out, same as `timeout(1)`.
+const EXIT_TIMEOUT: u8 = 124;
+
+/// Runs `tree wait` subcommand. Returns the exit code, which tells if the
+/// timeout has expired.
+pub fn run(args: &ArgMatches) -> Result<ExitCode> {
+    let timeout = args.get_one::<Duration>("timeout").copied();
+
+    let mut tracker = Tracker::new(piderator::args_or_stdin(args))?;
+
+    let deadline = timeout.map(|timeout| Instant::now() + timeout);
+
+    // command names can not be read any more once a process has exited
+    let mut names: HashMap<Task, String> = HashMap::new();
+    let mut last = vec![];
+
+    loop {
+        let alive = tracker.alive()?;
+
+        if alive.is_empty() {
+            break;
+        }
+
+        names = alive
+            .iter()
+            .map(|&task| {
+                let name = names.remove(&task).unwrap_or_else(|| task.comm());
+                (task, name)
+            })
+            .collect();
+
+        last = alive;
+
+        if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+            let alive = last
+                .iter()
+                .map(|task| format!("{} {}", task.pid, names[task]))
+                .collect::<Vec<_>>();
+
+            log::error(format!(
+                "timed out, {} processes still alive: {}",
+                alive.len(),
+                alive.join(", ")
+            ));
+
+            return Ok(ExitCode::from(EXIT_TIMEOUT));
+        }
+
+        thread::sleep(POLL_INTERVAL);
+    }
+
+    for task in last {
+        println!("{} {} exited last", task.pid, names[&task]);
+    }
+
+    Ok(ExitCode::SUCCESS)
+}
@@ -0,0 +1,205 @@
+//! Resource usage of reaped processes, aggregated per command name.
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::time::Duration;
+
+use crate::util;
+
+/// Resource usage of one or more processes.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Usage {
+    pub processes: u64,
+    pub user: Duration,
+    pub system: Duration,
+
+    /// Peak resident memory in bytes.
+    pub max_rss: u64,
+
+    /// Bytes read from and written to storage.
+    pub read_bytes: u64,
+    pub write_bytes: u64,
+}
+
+impl Usage {
+    /// Returns the usage of a single process from what `wait4(2)` reports and
+    /// its `/proc/<pid>/io`. Both include the children it waited for.
+    pub fn from_rusage(
+        rusage: &libc::rusage,
+        read_bytes: u64,
+        write_bytes: u64,
+    ) -> Self {
+        let time = |time: libc::timeval| {
+            let secs = u64::try_from(time.tv_sec).unwrap_or_default();
+            let micros = u64::try_from(time.tv_usec).unwrap_or_default();
+
+            Duration::from_secs(secs) + Duration::from_micros(micros)
+        };
+
+        Self {
+            processes: 1,
+            user: time(rusage.ru_utime),
+            system: time(rusage.ru_stime),
+            max_rss: u64::try_from(rusage.ru_maxrss).unwrap_or_default()
+                * 1024,
+            read_bytes,
+            write_bytes,
+        }
+    }
+
+    fn add(&mut self, other: &Self) {
+        self.processes += other.processes;
+        self.user += other.user;
+        self.system += other.system;
+        self.max_rss = self.max_rss.max(other.max_rss);
+        self.read_bytes += other.read_bytes;
+        self.write_bytes += other.write_bytes;
+    }
+
+    fn to_json(self) -> String {
+        format!(
+            "{{\"processes\": {}, \"user_seconds\": {:.3}, \
+             \"system_seconds\": {:.3}, \"max_rss_bytes\": {}, \
+             \"read_bytes\": {}, \"write_bytes\": {}}}",
+            self.processes,
+            self.user.as_secs_f64(),
+            self.system.as_secs_f64(),
+            self.max_rss,
+            self.read_bytes,
+            self.write_bytes,
+        )
+    }
+}
+
+/// Usage per command name.
+#[derive(Debug, Default)]
+pub struct Summary {
+    commands: BTreeMap<String, Usage>,
+}
+
+impl Summary {
+    /// Adds the usage of a process with the given command name.
+    pub fn add(&mut self, command: String, usage: &Usage) {
+        self.commands.entry(command).or_default().add(usage);
+    }
+
+    fn total(&self) -> Usage {
+        let mut total = Usage::default();
+
+        for usage in self.commands.values() {
+            total.add(usage);
+        }
+
+        total
+    }
+
+    /// Returns the summary as a table, one line per command and a total.
+    pub fn to_text(&self) -> String {
+        let width = self
+            .commands
+            .keys()
+            .map(|command| command.chars().count())
+            .chain([7])
+            .max()
+            .unwrap_or_default();
+
+        let mut text = format!(
+            "{:<width$} {:>6} {:>10} {:>10} {:>8} {:>8} {:>8}\n",
+            "COMMAND", "PROCS", "USER", "SYSTEM", "MAX_RSS", "READ", "WRITE"
+        );
+
+        let total = (String::from("total"), self.total());
+
+        for (command, usage) in
+            self.commands.iter().chain([(&total.0, &total.1)])
+        {
+            let _ = writeln!(
+                text,
+                "{command:<width$} {:>6} {:>9.2}s {:>9.2}s {:>8} {:>8} {:>8}",
+                usage.processes,
+                usage.user.as_secs_f64(),
+                usage.system.as_secs_f64(),
+                util::format_bytes(usage.max_rss),
+                util::format_bytes(usage.read_bytes),
+                util::format_bytes(usage.write_bytes),
+            );
+        }
+
+        text
+    }
+
+    /// Returns the summary as a JSON object.
+    pub fn to_json(&self) -> String {
+        let commands = self
+            .commands
+            .iter()
+            .map(|(command, usage)| {
+                format!("{}: {}", json_string(command), usage.to_json())
+            })
+            .collect::<Vec<_>>();
+
+        format!(
+            "{{\"total\": {}, \"commands\": {{{}}}}}",
+            self.total().to_json(),
+            commands.join(", ")
+        )
+    }
+}
+
+/// Returns a JSON string literal.
+fn json_string(s: &str) -> String {
+    let mut json = String::from("\"");
+
+    for c in s.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            '\t' => json.push_str("\\t"),
+            c if c.is_control() => {
+                let _ = write!(json, "\\u{:04x}", u32::from(c));
+            }
+            c => json.push(c),
+        }
+    }
+
+    json.push('"');
+    json
+}
+
+// ----------------------------------------------------------------------------
+// tests
+// ----------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::{Summary, Usage, json_string};
+
+    #[test]
+    fn json() {
+        assert_eq!(json_string("a\"b\\c\n"), r#""a\"b\\c\n""#);
+        assert_eq!(json_string("\u{1}"), r#""\u0001""#);
+
+        let mut summary = Summary::default();
+
+        let usage = Usage {
+            processes: 1,
+            max_rss: 1024,
+            ..Usage::default()
+        };
+
+        summary.add(String::from("cc"), &usage);
+        summary.add(String::from("cc"), &usage);
+
+        assert_eq!(
+            summary.to_json(),
+            "{\"total\": {\"processes\": 2, \"user_seconds\": 0.000, \
+             \"system_seconds\": 0.000, \"max_rss_bytes\": 1024, \
+             \"read_bytes\": 0, \"write_bytes\": 0}, \"commands\": \
+             {\"cc\": {\"processes\": 2, \"user_seconds\": 0.000, \
+             \"system_seconds\": 0.000, \"max_rss_bytes\": 1024, \
+             \"read_bytes\": 0, \"write_bytes\": 0}}}"
+        );
+    }
+}
@@ -1,2 +1,22 @@
 pub mod pid;
 pub mod piderator;
+
+/// Formats a number of bytes with a binary unit, e.g. `1.5G`.
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["K", "M", "G", "T", "P"];
+
+    if bytes < 1024 {
+        return format!("{bytes}B");
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    let mut value = bytes as f64 / 1024.0;
+    let mut unit = 0;
+
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+
+    format!("{value:.1}{}", UNITS[unit])
+}
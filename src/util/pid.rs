@@ -1,7 +1,7 @@
 use anyhow::Result;
 use procfs::process::Process;
 
-#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
 pub struct ProcessID(pub i32);
 
 impl ProcessID {
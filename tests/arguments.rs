@@ -33,3 +33,149 @@ fn pid() -> Result<(), Box<dyn Error>> {
 
     Ok(())
 }
+
+#[test]
+fn cpuset() -> Result<(), Box<dyn Error>> {
+    for cpuset in ["x", "3-1", "0-7:0", "0,,1"] {
+        let mut cmd = util::bin(&["tree", "modify", "affinity", cpuset, "1"])?;
+        cmd.assert().failure().stderr(
+            predicate::str::is_match("error: [Ii]nvalid value").unwrap(),
+        );
+    }
+
+    Ok(())
+}
+
+#[test]
+fn ioprio() -> Result<(), Box<dyn Error>> {
+    for level in ["x", "8", "10"] {
+        let mut cmd =
+            util::bin(&["tree", "modify", "ioprio", "be", level, "1"])?;
+        cmd.assert().failure().stderr(
+            predicate::str::is_match("error: [Ii]nvalid value").unwrap(),
+        );
+    }
+
+    // the idle class has no level, so it is taken as a PID
+    let mut cmd = util::bin(&["tree", "modify", "ioprio", "idle", "0"])?;
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::is_match("error: [Ii]nvalid value").unwrap());
+
+    Ok(())
+}
+
+#[test]
+fn limits() -> Result<(), Box<dyn Error>> {
+    let mut cmd = util::bin(&["tree", "show", "limits", "files", "1"])?;
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::is_match("error: [Ii]nvalid value").unwrap());
+
+    for args in [["limits", "1"].as_slice(), &["limits", "nofile", "1"]] {
+        let mut cmd = util::bin(&[&["tree", "show"], args].concat())?;
+        cmd.assert()
+            .success()
+            .stdout(predicate::str::contains("nofile"));
+    }
+
+    Ok(())
+}
+
+#[test]
+fn niceness() -> Result<(), Box<dyn Error>> {
+    for niceness in ["x", "20", "=-21", "+"] {
+        let mut cmd = util::bin(&["tree", "modify", "nice", niceness, "1"])?;
+        cmd.assert().failure().stderr(
+            predicate::str::is_match("error: [Ii]nvalid value").unwrap(),
+        );
+    }
+
+    Ok(())
+}
+
+#[test]
+fn oom_score_adj() -> Result<(), Box<dyn Error>> {
+    for value in ["x", "1001", "=-1001", "+", "-"] {
+        let mut cmd =
+            util::bin(&["tree", "modify", "oom_score_adj", value, "1"])?;
+        cmd.assert().failure().stderr(
+            predicate::str::is_match("error: [Ii]nvalid value").unwrap(),
+        );
+    }
+
+    Ok(())
+}
+
+#[test]
+fn sched() -> Result<(), Box<dyn Error>> {
+    for priority in ["x", "0", "100"] {
+        let mut cmd =
+            util::bin(&["tree", "modify", "sched", "fifo", priority, "1"])?;
+        cmd.assert().failure().stderr(
+            predicate::str::is_match("error: [Ii]nvalid value").unwrap(),
+        );
+    }
+
+    // non-realtime policies have no priority, so it is taken as a PID
+    let mut cmd = util::bin(&["tree", "modify", "sched", "batch", "0"])?;
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::is_match("error: [Ii]nvalid value").unwrap());
+
+    Ok(())
+}
+
+#[test]
+fn signal() -> Result<(), Box<dyn Error>> {
+    for signal in ["x", "0", "65", "SIGFOO"] {
+        let mut cmd =
+            util::bin(&["tree", "signal", "--dry-run", signal, "1"])?;
+        cmd.assert().failure().stderr(
+            predicate::str::is_match("error: [Ii]nvalid value").unwrap(),
+        );
+    }
+
+    Ok(())
+}
+
+#[test]
+fn enforce() -> Result<(), Box<dyn Error>> {
+    for interval in ["x", "1y", "2d", "0s", "0"] {
+        let mut cmd = util::bin(&[
+            "tree",
+            "enforce",
+            "--interval",
+            interval,
+            "nice",
+            "5",
+            "1",
+        ])?;
+        cmd.assert().failure().stderr(
+            predicate::str::is_match("error: [Ii]nvalid value").unwrap(),
+        );
+    }
+
+    for args in [["nice", "+5"], ["affinity", "-0"], ["affinity", "&0"]] {
+        let mut cmd =
+            util::bin(&[&["tree", "enforce"], &args[..], &["1"]].concat())?;
+        cmd.assert().failure().stderr(predicate::str::contains(
+            "relative adjustments can not be enforced",
+        ));
+    }
+
+    Ok(())
+}
+
+#[test]
+fn daemon() -> Result<(), Box<dyn Error>> {
+    for interval in ["x", "0ms"] {
+        let mut cmd =
+            util::bin(&["daemon", "--interval", interval, "--rules", "x"])?;
+        cmd.assert().failure().stderr(
+            predicate::str::is_match("error: [Ii]nvalid value").unwrap(),
+        );
+    }
+
+    Ok(())
+}
@@ -0,0 +1,115 @@
+mod util;
+
+use std::error::Error;
+use std::process::Command;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use procfs::process::Process;
+
+#[test]
+fn oom_score_adj() -> Result<(), Box<dyn Error>> {
+    let mut child = Command::new("sleep").arg("10").spawn()?;
+    let process = Process::new(i32::try_from(child.id())?)?;
+    let pid = child.id().to_string();
+
+    for (value, expected) in [("300", 300), ("-100", 200), ("+50", 250)] {
+        let mut cmd =
+            util::bin(&["tree", "modify", "oom_score_adj", value, &pid])?;
+        cmd.assert().success();
+
+        assert_eq!(process.oom_score_adj()?, expected);
+    }
+
+    child.kill()?;
+    child.wait()?;
+
+    Ok(())
+}
+
+#[test]
+fn verbose() -> Result<(), Box<dyn Error>> {
+    let mut child = Command::new("sleep").arg("10").spawn()?;
+    let pid = child.id().to_string();
+
+    util::bin(&["tree", "modify", "nice", "3", &pid])?
+        .assert()
+        .success();
+
+    for (args, expected) in [
+        (["nice", "5"], "sleep: 3 -> 5"),
+        (["sched", "batch"], "sleep: other -> batch"),
+    ] {
+        let mut cmd = util::bin(&[&["tree", "modify"], &args[..]].concat())?;
+        cmd.args(["--verbose", &pid])
+            .assert()
+            .success()
+            .stderr(predicates::str::contains(expected));
+    }
+
+    child.kill()?;
+    child.wait()?;
+
+    Ok(())
+}
+
+#[test]
+fn enforce() -> Result<(), Box<dyn Error>> {
+    // the child is forked after the first round, inheriting the value of the
+    // protected root
+    let mut root = Command::new("sh")
+        .args(["-c", "sleep 1; sleep 10 & wait"])
+        .spawn()?;
+
+    let root_process = Process::new(i32::try_from(root.id())?)?;
+    let pid = root_process.pid.to_string();
+
+    let mut enforce = Command::new(env!("CARGO_BIN_EXE_psfu"))
+        .args(["tree", "enforce", "--interval", "100ms", "oom_score_adj"])
+        .args(["--protect-root", "500", &pid])
+        .spawn()?;
+
+    let values = || {
+        children(root_process.pid)?
+            .iter()
+            .map(|child| Ok((child.cmdline()?, child.oom_score_adj()?)))
+            .collect::<Result<Vec<_>, Box<dyn Error>>>()
+    };
+
+    let expected = [(vec![String::from("sleep"), String::from("10")], 500)];
+    let deadline = Instant::now() + Duration::from_secs(10);
+
+    // the child shows up after a second and is modified within an interval
+    let mut last = values();
+    while Instant::now() < deadline
+        && !last.as_ref().is_ok_and(|values| values == &expected)
+    {
+        thread::sleep(Duration::from_millis(50));
+        last = values();
+    }
+
+    let root_value = root_process.oom_score_adj();
+
+    enforce.kill()?;
+    enforce.wait()?;
+
+    for child in children(root_process.pid)? {
+        Command::new("kill").arg(child.pid.to_string()).status()?;
+    }
+
+    root.kill()?;
+    root.wait()?;
+
+    assert_eq!(last?, expected);
+    assert_eq!(root_value?, Process::myself()?.oom_score_adj()?);
+
+    Ok(())
+}
+
+/// Returns the children of a process.
+fn children(pid: i32) -> Result<Vec<Process>, Box<dyn Error>> {
+    Ok(procfs::process::all_processes()?
+        .flatten()
+        .filter(|process| process.stat().is_ok_and(|stat| stat.ppid == pid))
+        .collect())
+}
@@ -0,0 +1,51 @@
+mod util;
+
+use std::error::Error;
+
+use predicates::prelude::*;
+
+#[test]
+fn settings() -> Result<(), Box<dyn Error>> {
+    let output =
+        util::bin(&["run", "--nice", "5", "--oom-score-adj", "300", "--"])?
+            .args(["sh", "-c", "cat /proc/self/stat /proc/self/oom_score_adj"])
+            .output()?;
+
+    assert!(output.status.success());
+
+    let stdout = String::from_utf8(output.stdout)?;
+    let mut lines = stdout.lines();
+
+    // the fields after the command name, starting with the state
+    let stat = lines.next().ok_or("stat is missing")?;
+    let (_, fields) = stat.rsplit_once(") ").ok_or("stat has no comm")?;
+    let fields = fields.split(' ').collect::<Vec<_>>();
+
+    assert_eq!(fields[16], "5", "niceness");
+    assert_eq!(lines.next(), Some("300"), "oom score adjustment");
+
+    Ok(())
+}
+
+#[test]
+fn affinity() -> Result<(), Box<dyn Error>> {
+    util::bin(&["run", "--affinity", "0", "--"])?
+        .args(["grep", "Cpus_allowed_list", "/proc/self/status"])
+        .assert()
+        .success()
+        .stdout(predicate::str::is_match(r"Cpus_allowed_list:\s+0\n")?);
+
+    Ok(())
+}
+
+#[test]
+fn usage() -> Result<(), Box<dyn Error>> {
+    // the orphaned sleep is reaped too, after the command has exited
+    util::bin(&["run", "--usage=json", "--"])?
+        .args(["sh", "-c", "sleep 0.1 & exit 3"])
+        .assert()
+        .code(3)
+        .stderr(predicate::str::contains("\"sleep\""));
+
+    Ok(())
+}
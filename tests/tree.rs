@@ -0,0 +1,157 @@
+mod util;
+
+use std::error::Error;
+use std::io::{BufRead, BufReader};
+use std::os::unix::process::ExitStatusExt;
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use predicates::prelude::*;
+use procfs::process::{ProcState, Process};
+
+#[test]
+fn terminate_stopped() -> Result<(), Box<dyn Error>> {
+    let mut child = Command::new("sleep").arg("10").spawn()?;
+    let pid = child.id().to_string();
+
+    let child_pid = i32::try_from(child.id())?;
+
+    Command::new("kill").args(["-STOP", &pid]).status()?;
+    assert!(poll(|| state(child_pid) == Some(ProcState::Stopped)));
+
+    // a stopped process acts on SIGTERM only once it is continued
+    let start = Instant::now();
+
+    let mut cmd = util::bin(&["tree", "terminate", "--grace", "10s", &pid])?;
+    cmd.assert().success();
+
+    assert!(start.elapsed() < Duration::from_secs(5));
+    assert_eq!(child.wait()?.signal(), Some(libc::SIGTERM));
+
+    Ok(())
+}
+
+#[test]
+fn terminate_escalation() -> Result<(), Box<dyn Error>> {
+    let mut child = Command::new("sh")
+        .args(["-c", "trap '' TERM; echo; sleep 10"])
+        .stdout(Stdio::piped())
+        .spawn()?;
+    let pid = child.id().to_string();
+
+    // wait for the trap to be set
+    let stdout = child.stdout.take().ok_or("stdout is piped")?;
+    BufReader::new(stdout).read_line(&mut String::new())?;
+
+    let mut cmd = util::bin(&["tree", "terminate", "--grace", "200ms", &pid])?;
+    cmd.assert()
+        .success()
+        .stderr(predicate::str::contains("still alive after"));
+
+    assert_eq!(child.wait()?.signal(), Some(libc::SIGKILL));
+
+    Ok(())
+}
+
+#[test]
+fn suspend_resume() -> Result<(), Box<dyn Error>> {
+    let mut root =
+        Command::new("sh").args(["-c", "sleep 10 & wait"]).spawn()?;
+    let pid = root.id().to_string();
+
+    let root_pid = i32::try_from(root.id())?;
+    assert!(poll(|| children(root_pid).len() == 1));
+
+    let tree = || {
+        let mut states = vec![state(root_pid)];
+        states.extend(children(root_pid).into_iter().map(state));
+        states
+    };
+
+    util::bin(&["tree", "suspend", &pid])?.assert().success();
+    assert!(poll(|| tree() == [Some(ProcState::Stopped); 2]));
+
+    util::bin(&["tree", "resume", &pid])?.assert().success();
+    assert!(poll(|| tree() == [Some(ProcState::Sleeping); 2]));
+
+    for child in children(root_pid) {
+        Command::new("kill").arg(child.to_string()).status()?;
+    }
+
+    root.wait()?;
+
+    Ok(())
+}
+
+#[test]
+fn wait() -> Result<(), Box<dyn Error>> {
+    let mut child = Command::new("sleep").arg("0.2").spawn()?;
+    let pid = child.id().to_string();
+
+    // the exited child stays a zombie until reaped below, which counts as
+    // exited
+    util::bin(&["tree", "wait", "--timeout", "10s", &pid])?
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("sleep exited last"));
+
+    child.wait()?;
+
+    Ok(())
+}
+
+#[test]
+fn wait_timeout() -> Result<(), Box<dyn Error>> {
+    let mut child = Command::new("sleep").arg("10").spawn()?;
+    let pid = child.id().to_string();
+
+    util::bin(&["tree", "wait", "--timeout", "100ms", &pid])?
+        .assert()
+        .code(124)
+        .stderr(predicate::str::contains("timed out"));
+
+    child.kill()?;
+    child.wait()?;
+
+    Ok(())
+}
+
+// ----------------------------------------------------------------------------
+// helper
+// ----------------------------------------------------------------------------
+
+/// Returns the state of a process, or `None` once it has exited.
+fn state(pid: i32) -> Option<ProcState> {
+    match Process::new(pid).ok()?.stat().ok()?.state().ok()? {
+        ProcState::Zombie | ProcState::Dead => None,
+        state => Some(state),
+    }
+}
+
+/// Returns the PIDs of the children of a process.
+fn children(pid: i32) -> Vec<i32> {
+    procfs::process::all_processes()
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter_map(|process| process.stat().ok())
+        .filter(|stat| stat.ppid == pid)
+        .map(|stat| stat.pid)
+        .collect()
+}
+
+/// Polls a condition until it holds, or gives up after a generous deadline.
+fn poll(condition: impl Fn() -> bool) -> bool {
+    let deadline = Instant::now() + Duration::from_secs(10);
+
+    while Instant::now() < deadline {
+        if condition() {
+            return true;
+        }
+
+        thread::sleep(Duration::from_millis(10));
+    }
+
+    false
+}